@@ -13,6 +13,7 @@ test_endpoint() {
     local path="$2" 
     local data="$3"
     local description="$4"
+    local header="${5:-X-Test: none}"
     
     echo "Testing: $description"
     echo "  $method $BASE_URL$path"
@@ -21,9 +22,10 @@ test_endpoint() {
         echo "  Data: $data"
         local response=$(curl -s -X "$method" "$BASE_URL$path" \
             -H "Content-Type: application/json" \
+            -H "$header" \
             -d "$data")
     else
-        local response=$(curl -s -X "$method" "$BASE_URL$path")
+        local response=$(curl -s -X "$method" "$BASE_URL$path" -H "$header")
     fi
     
     # Check if response looks like HTML
@@ -37,20 +39,70 @@ test_endpoint() {
     echo ""
 }
 
+# Fetch a path and abort the run unless the response is an HTML document
+expect_html() {
+    local path="$1"
+    local description="$2"
+
+    echo "Testing: $description"
+    echo "  GET $BASE_URL$path"
+    local response=$(curl -s "$BASE_URL$path")
+    if [[ "$response" == *"<!DOCTYPE"* ]] || [[ "$response" == *"<html"* ]] || [[ "$response" == *"<HTML"* ]]; then
+        echo "  Response: [HTML Document - UI loaded successfully]"
+    else
+        echo "  FAILED: expected HTML, got: $response"
+        exit 1
+    fi
+    echo ""
+}
+
+# Call a task handler through the JSON-RPC POST path and print the raw response
+call_handler() {
+    curl -s -X POST "$BASE_URL/api" -H "Content-Type: application/json" -d "$1"
+}
+
+# Extract the first "id" value from a JSON response on stdin
+first_id() {
+    grep -o '"id":"[^"]*"' | head -1 | cut -d'"' -f4
+}
+
 echo "=== SPECIFIC PATH HANDLERS ==="
 
+test_endpoint "GET" "/health" "" " GET /health (health handler)"
+test_endpoint "GET" "/ping" "" " GET /ping (should return pong)"
+test_endpoint "GET" "/version" "" " GET /version (should report crate version)"
+test_endpoint "GET" "/whoami" "" " GET /whoami (should report the request source)"
+test_endpoint "GET" "/time" "" " GET /time (should return server unix millis)"
 test_endpoint "GET" "/users" "" " GET /users (specific handler)"
 test_endpoint "POST" "/users" '{"CreateUser": {"message": "John Doe", "id": 1}}' " POST /users (specific handler with params)"
 test_endpoint "GET" "/posts" "" " GET /posts (specific handler)"
 test_endpoint "POST" "/api/data" '{"ProcessData": {"message": "test data", "id": 42}}' " POST /api/data (specific handler with params)"
+test_endpoint "POST" "/echo-delay" '{"EchoDelay": {"message": "delayed", "delay_ms": 1000}}' " POST /echo-delay (specific handler, ~1s delay)"
+test_endpoint "POST" "/status" '{"ForceStatus": {"code": 204}}' " POST /status with 204 (should return Ok)"
+test_endpoint "POST" "/status" '{"ForceStatus": {"code": 503}}' " POST /status with 503 (should return Err)"
+test_endpoint "POST" "/echo-raw" '{"any": "payload", "n": 1}' " POST /echo-raw (should echo the raw body)"
+test_endpoint "POST" "/compute" '{"Compute": {"message": "math", "id": 12}}' " POST /compute with id (should return 12 squared is 144)"
+test_endpoint "POST" "/compute" '{"Compute": {"message": "math", "id": null}}' " POST /compute without id (should return Err)"
+test_endpoint "POST" "/slow-steps" '{"SlowSteps": {"steps": 3, "step_ms": 200}}' " POST /slow-steps 3x200ms (should take about 600ms)"
+test_endpoint "POST" "/delay-random" '{"DelayRandom": {"min_ms": 100, "max_ms": 500}}' " POST /delay-random 100-500ms (should report the chosen delay)"
+test_endpoint "POST" "/delay-random" '{"DelayRandom": {"min_ms": 500, "max_ms": 100}}' " POST /delay-random min > max (should return Err)"
+test_endpoint "POST" "/bulk-echo" '{"BulkEcho": ["abc", "de", ""]}' " POST /bulk-echo (should return 5)"
+for attempt in 1 2 3; do
+  test_endpoint "POST" "/flaky" '{"Flaky": ""}' " POST /flaky attempt $attempt (should fail twice, then succeed)"
+done
 
 echo "=== DYNAMIC FALLBACK HANDLERS ==="
 
 test_endpoint "GET" "/api/unknown" "" " GET /api/unknown (should hit API GET fallback)"
+test_endpoint "GET" "/api/echo?foo=bar&x=1&x=2&msg=hello%20world" "" " GET /api/echo with query (should echo parsed query, x=2)"
 test_endpoint "GET" "/admin/dashboard" "" " GET /admin/dashboard (should hit admin GET fallback)" 
 test_endpoint "GET" "/test/something" "" " GET /test/something (should hit test GET fallback)"
+expect_html "/" " GET / (should reach the UI, not the GET fallback)"
+expect_html "/index.html" " GET /index.html (should reach the UI, not the GET fallback)"
 
 test_endpoint "POST" "/api/upload" '{"HandlePostFallback": {"message": "upload data", "id": 99}}' "🔄 POST /api/upload (should hit POST fallback)"
+test_endpoint "POST" "/api/upload" '{"HandlePostFallback": {"message": "tagged", "id": 7}}' "🔄 POST /api/upload with X-Request-Id (should echo request_id)" "X-Request-Id: test-req-123"
+test_endpoint "GET" "/api/unknown" "" " GET /api/unknown with X-Test header (should echo headers)" "X-Test: round-trip"
 test_endpoint "POST" "/other/endpoint" '{"HandlePostFallback": {"message": "other data", "id": 88}}' "🔄 POST /other/endpoint (should hit POST fallback)"
 
 echo "=== CATCH-ALL HANDLER ==="
@@ -59,10 +111,64 @@ test_endpoint "PUT" "/anything" "" " PUT /anything (should hit catch-all)"
 test_endpoint "DELETE" "/whatever" "" " DELETE /whatever (should hit catch-all)"
 test_endpoint "PATCH" "/some/path" "" " PATCH /some/path (should hit catch-all)"
 
+test_endpoint "OPTIONS" "/users" "" " OPTIONS /users (should list GET, POST)"
+test_endpoint "OPTIONS" "/api/unknown" "" " OPTIONS /api/unknown (should list fallback methods)"
+echo "Testing:  HEAD /anything (should hit HEAD catch-all, empty body)"
+echo "  HEAD $BASE_URL/anything"
+echo "  Status: $(curl -s -o /dev/null -w '%{http_code}' -I "$BASE_URL/anything")"
+echo ""
+
 echo "=== ERROR CASES ==="
 
 test_endpoint "POST" "/users" '{"WrongHandler": {"message": "test"}}' " POST /users with wrong handler name"
 test_endpoint "POST" "/users" 'invalid json' " POST /users with invalid JSON"
 test_endpoint "POST" "/users" "" " POST /users with no body"
 
+echo "=== TASK HANDLERS ==="
+
+# Three-level chain: root <- child <- grandchild
+root_id=$(call_handler '{"AddTask": ["cascade root", null, null]}' | first_id)
+child_id=$(call_handler "{\"AddTask\": [\"cascade child\", null, \"$root_id\"]}" | first_id)
+grandchild_id=$(call_handler "{\"AddTask\": [\"cascade grandchild\", null, \"$child_id\"]}" | first_id)
+test_endpoint "POST" "/api" "{\"DeleteTask\": \"$root_id\"}" " DeleteTask on root (should delete root, child and grandchild)"
+test_endpoint "POST" "/api" "{\"GetTask\": \"$grandchild_id\"}" " GetTask on grandchild (should be not found)"
+test_endpoint "POST" "/api" "{\"TaskHistory\": \"$grandchild_id\"}" " TaskHistory on grandchild (should show Added then Deleted)"
+
+call_handler '{"AddTask": ["since check", null, null]}' > /dev/null
+test_endpoint "POST" "/api" '{"GetTasksSince": 0}' " GetTasksSince 0 (should return all tasks, oldest first)"
+test_endpoint "POST" "/api" '{"GetTasksSince": 32503680000000}' " GetTasksSince far future (should return empty list)"
+
+test_endpoint "POST" "/api" '{"FindInvalidTasks": ""}' " FindInvalidTasks (should return empty list)"
+test_endpoint "POST" "/api" '{"GetTasksGrouped": ""}' " GetTasksGrouped (should split pending and completed)"
+test_endpoint "POST" "/api" '{"GetEvents": 0}' " GetEvents 0 (should list every add, toggle and delete so far)"
+test_endpoint "POST" "/api" '{"GetTasksAs": "Csv"}' " GetTasksAs Csv (should return id,text,completed rows)"
+test_endpoint "POST" "/api" '{"GetTasksAs": "PlainText"}' " GetTasksAs PlainText (should return [x]/[ ] lines)"
+test_endpoint "POST" "/api" '{"ImportCsv": ["text,completed\nfrom csv,false\n\"quoted, text\",true", false]}' " ImportCsv two rows (should return 2)"
+test_endpoint "POST" "/api" '{"ImportCsv": ["id,text,completed\n1,exported,true\n2,\"multi\nline\",false\n", false]}' " ImportCsv GetTasksAs Csv output (should return 2)"
+test_endpoint "POST" "/api" '{"ImportCsv": ["text,completed\nbad row", false]}' " ImportCsv malformed row (should return Err naming line 2)"
+test_endpoint "POST" "/api" '{"ArchiveCompleted": ""}' " ArchiveCompleted (should return number of completed tasks moved)"
+test_endpoint "POST" "/api" '{"GetArchived": ""}' " GetArchived (should list only completed tasks)"
+test_endpoint "POST" "/api" '{"FuzzySearch": ["sinse", 1]}' " FuzzySearch with a typo (should find the since check task)"
+test_endpoint "POST" "/api" '{"AnyTask": {"completed": false, "text_contains": "SINCE"}}' " AnyTask pending + text (should return true)"
+test_endpoint "POST" "/api" '{"CountMatching": {"completed": true, "text_contains": null}}' " CountMatching completed (should count completed tasks)"
+test_endpoint "POST" "/api" '{"PruneCompletedBefore": 0}' " PruneCompletedBefore 0 (should remove nothing)"
+test_endpoint "POST" "/api" '{"ValidateTaskText": "   "}' " ValidateTaskText blank (should return empty text error)"
+test_endpoint "POST" "/api" '{"GetTaskTree": ""}' " GetTaskTree (should nest subtasks under their parents)"
+test_endpoint "POST" "/api" '{"ResetCallCount": ""}' " ResetCallCount (should return calls so far)"
+test_endpoint "POST" "/api" '{"GetCallCount": ""}' " GetCallCount right after reset (should return 1)"
+test_endpoint "POST" "/api" '{"RenumberTasks": ""}' " RenumberTasks (should map every old id to 1, 2, ...)"
+
+# Bulk seed with autosave off, then persist once
+test_endpoint "POST" "/api" '{"SetAutosave": false}' " SetAutosave false (should return false)"
+call_handler '{"AddTasks": ["seed one", "seed two", "seed three"]}' > /dev/null
+test_endpoint "POST" "/api" '{"ForceSave": ""}' " ForceSave (should return Ok)"
+test_endpoint "POST" "/api" '{"SetAutosave": true}' " SetAutosave true (should return true)"
+
+# Burst past the add_task rate limit once the window has cleared
+sleep 1
+for i in $(seq 1 10); do
+  call_handler "{\"AddTask\": [\"burst $i\", null, null]}" > /dev/null
+done
+test_endpoint "POST" "/api" '{"AddTask": ["burst 11", null, null]}' " AddTask past the rate limit (should return rate limited)"
+
 echo "Test completed!" 
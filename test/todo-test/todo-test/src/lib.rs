@@ -1,4 +1,4 @@
-use caller_utils::{TodoState, TodoItem};
+use caller_utils::{Priority, TodoState, TodoItem};
 use caller_utils::todo::{export_state_local_rpc, import_state_local_rpc};
 // Add this import here, as fail! is expanded in this file
 use crate::hyperware::process::tester::{FailResponse, Response as TesterResponse};
@@ -24,9 +24,13 @@ async_test_suite!(
         // 1. Define initial state (dummy data)
         let initial_state = TodoState {
             tasks: vec![
-                TodoItem { id: "1".to_string(), text: "Task 1".to_string(), completed: false },
-                TodoItem { id: "2".to_string(), text: "Task 2".to_string(), completed: true },
+                TodoItem { id: "1".to_string(), text: "Task 1".to_string(), completed: false, priority: Priority::Medium, due_at: None, tags: vec![], parent_id: None, version: 0, created_at_ms: 1_700_000_000_000, updated_at_ms: 1_700_000_000_000 },
+                TodoItem { id: "2".to_string(), text: "Task 2".to_string(), completed: true, priority: Priority::High, due_at: Some(1_700_000_000_000), tags: vec!["home".to_string()], parent_id: Some("1".to_string()), version: 3, created_at_ms: 1_700_000_000_001, updated_at_ms: 1_700_000_000_500 },
             ],
+            archived: vec![
+                TodoItem { id: "3".to_string(), text: "Task 3".to_string(), completed: true, priority: Priority::Low, due_at: None, tags: vec![], parent_id: None, version: 1, created_at_ms: 1_699_999_999_999, updated_at_ms: 1_700_000_000_002 },
+            ],
+            renumbered_ids: vec![],
         };
         print_to_terminal(0, &format!("Initial state: {:?}", initial_state));
 
@@ -82,11 +86,23 @@ async_test_suite!(
             ));
         }
 
+        // Field-by-field comparison, shared by the task list and the archive
+        let same_task = |a: &TodoItem, b: &TodoItem| {
+            a.id == b.id &&
+            a.text == b.text &&
+            a.completed == b.completed &&
+            a.priority == b.priority &&
+            a.due_at == b.due_at &&
+            a.tags == b.tags &&
+            a.parent_id == b.parent_id &&
+            a.version == b.version &&
+            a.created_at_ms == b.created_at_ms &&
+            a.updated_at_ms == b.updated_at_ms
+        };
+
         // Iterate and compare each task field by field
         for (initial_task, exported_task) in initial_state.tasks.iter().zip(exported_data.tasks.iter()) {
-            if initial_task.id != exported_task.id ||
-               initial_task.text != exported_task.text ||
-               initial_task.completed != exported_task.completed {
+            if !same_task(initial_task, exported_task) {
                 fail!(format!(
                     "Task mismatch detected.\nExpected Task: {:?}\nGot Task: {:?}",
                     initial_task, // Assumes TodoItem derives Debug
@@ -94,6 +110,23 @@ async_test_suite!(
                 ));
             }
         }
+        // The archive is persisted alongside the task list
+        if initial_state.archived.len() != exported_data.archived.len() {
+            fail!(format!(
+                "Archive lengths differ. Expected: {}, Got: {}",
+                initial_state.archived.len(),
+                exported_data.archived.len()
+            ));
+        }
+        for (initial_task, exported_task) in initial_state.archived.iter().zip(exported_data.archived.iter()) {
+            if !same_task(initial_task, exported_task) {
+                fail!(format!(
+                    "Archived task mismatch detected.\nExpected Task: {:?}\nGot Task: {:?}",
+                    initial_task,
+                    exported_task
+                ));
+            }
+        }
         print_to_terminal(0, "Exported state matches initial state.");
 
         Ok(())
@@ -1,21 +1,303 @@
 use hyperprocess_macro::hyperprocess;
-use hyperware_app_common::{get_http_method, get_path};
-use hyperware_process_lib::http::server::{send_ws_push, WsMessageType};
-use hyperware_process_lib::{kiprintln, LazyLoadBlob};
+use hyperware_app_common::{get_http_method, get_path, sleep, APP_CONTEXT};
+use hyperware_process_lib::http::server::{
+    send_ws_push, HttpServerRequest, IncomingHttpRequest, WsMessageType,
+};
+use hyperware_process_lib::{get_blob, kiprintln, set_state, LazyLoadBlob};
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::cell::Cell;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::time::{SystemTime, UNIX_EPOCH};
 use uuid::Uuid; 
 
 // =============================================================================
 // CORE TODO APPLICATION DATA STRUCTURES
 // =============================================================================
 
+/// Task priority level
+#[derive(PartialEq, Clone, Debug, Default, Serialize, Deserialize)]
+pub enum Priority {
+    Low,
+    #[default]
+    Medium,
+    High,
+}
+
 /// Core todo item with unique ID, text content, and completion status
 #[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
 pub struct TodoItem {
     id: String,
     text: String,
     completed: bool,
+    /// Task priority (missing in older saved state, so defaulted)
+    #[serde(default)]
+    priority: Priority,
+    /// Optional due date in unix milliseconds
+    #[serde(default)]
+    due_at: Option<u64>,
+    /// Free-form labels, without duplicates
+    #[serde(default)]
+    tags: Vec<String>,
+    /// Id of the parent task, if this is a subtask
+    #[serde(default)]
+    parent_id: Option<String>,
+    /// Bumped on every mutation, for optimistic concurrency checks
+    #[serde(default)]
+    version: u64,
+    /// Unix millis when the task was created (0 for tasks saved before this field existed)
+    #[serde(default)]
+    created_at_ms: u64,
+    /// Unix millis of the last mutation, equal to `created_at_ms` until first touched
+    #[serde(default)]
+    updated_at_ms: u64,
+}
+
+impl TodoItem {
+    fn new(text: String) -> Self {
+        let now = now_ms();
+        Self {
+            id: Uuid::new_v4().to_string(),
+            text,
+            completed: false,
+            priority: Priority::default(),
+            due_at: None,
+            tags: Vec::new(),
+            parent_id: None,
+            version: 0,
+            created_at_ms: now,
+            updated_at_ms: now,
+        }
+    }
+
+    /// Record a mutation of this task
+    fn touch(&mut self) {
+        self.version += 1;
+        self.updated_at_ms = now_ms();
+    }
+}
+
+/// Maximum task text length (in characters) accepted by `add_task_strict`
+const MAX_TASK_TEXT_LEN: usize = 280;
+
+/// Maximum number of tasks the list may hold
+const MAX_TASKS: usize = 1000;
+
+/// Maximum number of `add_task` calls accepted per rate-limit window
+const RATE_LIMIT_MAX_ADDS: usize = 10;
+
+/// Length of the sliding rate-limit window for `add_task`, in milliseconds
+const RATE_LIMIT_WINDOW_MS: u64 = 1000;
+
+/// Paths bound in the `endpoints` list below; keep in sync when adding bindings
+const ENDPOINT_PATHS: &[&str] = &[
+    "/health",
+    "/ping",
+    "/version",
+    "/ws",
+    "/users",
+    "/posts",
+    "/api",
+    "/admin",
+    "/test",
+    "/api/unknown",
+    "/admin/dashboard",
+    "/test/something",
+    "/api/upload",
+    "/other/endpoint",
+    "/anything",
+    "/whatever",
+    "/some/path",
+    "/api/data",
+    "/echo-delay",
+    "/status",
+    "/api/echo",
+    "/echo-raw",
+    "/compute",
+    "/slow-steps",
+    "/whoami",
+    "/delay-random",
+    "/time",
+    "/flaky",
+    "/bulk-echo",
+];
+
+/// Current unix time in milliseconds
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+/// Field to sort tasks by in `get_tasks_sorted`
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub enum SortKey {
+    /// Case-insensitive text order
+    Text,
+    /// Pending tasks first, then completed
+    Completed,
+    Id,
+}
+
+/// Output encoding for `get_tasks_as`
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub enum ExportFormat {
+    Json,
+    /// `id,text,completed` header followed by one row per task
+    Csv,
+    /// One `[x]`/`[ ]` prefixed line per task
+    PlainText,
+}
+
+/// A page of tasks along with the total task count
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct PagedTasks {
+    pub items: Vec<TodoItem>,
+    /// Total number of tasks before paging
+    pub total: usize,
+    pub offset: usize,
+    pub limit: usize,
+}
+
+/// Error returned by task handlers, tagged by kind so clients can match on it
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub enum TodoError {
+    /// The referenced task (or other item) does not exist
+    NotFound(String),
+    /// The request itself is malformed or fails validation
+    Validation(String),
+    /// The request is valid but clashes with current state
+    Conflict(String),
+    /// The server failed for reasons unrelated to the request (e.g. serialization)
+    Internal(String),
+}
+
+impl TodoError {
+    fn task_not_found(task_id: &str) -> Self {
+        TodoError::NotFound(format!("Task with id '{}' not found", task_id))
+    }
+
+    fn empty_text() -> Self {
+        TodoError::Validation("Task text cannot be empty".to_string())
+    }
+}
+
+impl std::fmt::Display for TodoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TodoError::NotFound(message)
+            | TodoError::Validation(message)
+            | TodoError::Conflict(message)
+            | TodoError::Internal(message) => f.write_str(message),
+        }
+    }
+}
+
+/// Partial update for `patch_task`; `None` fields are left unchanged
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct TaskPatch {
+    pub text: Option<String>,
+    pub completed: Option<bool>,
+}
+
+/// Conditions a task must all satisfy; `None` fields match anything
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct TaskFilter {
+    pub completed: Option<bool>,
+    /// Case-insensitive substring of the task text
+    pub text_contains: Option<String>,
+}
+
+impl TaskFilter {
+    fn matches(&self, task: &TodoItem) -> bool {
+        self.completed.is_none_or(|c| task.completed == c)
+            && self
+                .text_contains
+                .as_ref()
+                .is_none_or(|needle| task.text.to_lowercase().contains(&needle.to_lowercase()))
+    }
+}
+
+/// A task with its subtasks, as returned by `get_task_tree`
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct TaskNode {
+    pub item: TodoItem,
+    pub children: Vec<TaskNode>,
+}
+
+/// Outcome of `add_tasks`: the created items and how many did not fit under `MAX_TASKS`
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct BulkAddResult {
+    pub added: Vec<TodoItem>,
+    pub skipped: usize,
+}
+
+/// Ids removed by `delete_task`: the task itself followed by its descendants
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct DeleteResult {
+    pub deleted_ids: Vec<String>,
+}
+
+/// A single mutation recorded in the event log
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub enum TaskEvent {
+    Added { id: String },
+    Toggled { id: String, completed: bool },
+    Deleted { id: String },
+}
+
+impl TaskEvent {
+    /// Id of the task the event concerns
+    fn task_id(&self) -> &str {
+        match self {
+            TaskEvent::Added { id } | TaskEvent::Toggled { id, .. } | TaskEvent::Deleted { id } => {
+                id
+            }
+        }
+    }
+}
+
+/// Task tallies; `completed + pending == total` always holds
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct TaskCounts {
+    pub total: usize,
+    pub completed: usize,
+    pub pending: usize,
+}
+
+/// Tasks partitioned by completion, each group in list order
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct GroupedTasks {
+    pub pending: Vec<TodoItem>,
+    pub completed: Vec<TodoItem>,
+}
+
+/// Word-count aggregates over task text, as returned by `word_stats`
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct WordStats {
+    pub total_tasks: usize,
+    pub total_words: usize,
+    /// 0.0 when there are no tasks
+    pub avg_words: f64,
+    /// Task with the most words (earliest wins ties)
+    pub longest_task_id: Option<String>,
+}
+
+/// Liveness report returned by `GET /health`
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct HealthStatus {
+    pub status: String,
+    pub task_count: usize,
+    pub ws_clients: usize,
+    pub uptime_ms: u64,
+}
+
+/// Build information returned by `GET /version`
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct VersionInfo {
+    pub name: String,
+    pub version: String,
+    pub wit_world: String,
 }
 
 /// Legacy response structure (kept for compatibility)
@@ -24,300 +306,2466 @@ pub struct Response {
     pub data: NestedData,
 }
 
-#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
-pub struct NestedData {
-    pub items: Vec<Item>,
-}
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct NestedData {
+    pub items: Vec<Item>,
+}
+
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct Item {
+    pub id: String,
+}
+
+// =============================================================================
+// TESTING/DEMO DATA STRUCTURES
+// =============================================================================
+
+/// Generic request structure for API testing and demos
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ApiRequest {
+    pub message: String,
+    pub id: Option<u32>,
+}
+
+/// Request for the configurable-delay echo handler
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DelayRequest {
+    pub message: String,
+    pub delay_ms: u64,
+}
+
+/// Request for the stepped slow-response handler
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StepRequest {
+    pub steps: u32,
+    pub step_ms: u64,
+}
+
+/// Inclusive delay range for the random-delay handler
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RangeRequest {
+    pub min_ms: u64,
+    pub max_ms: u64,
+}
+
+/// Request for the forced-status handler
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StatusRequest {
+    pub code: u16,
+}
+
+/// Upper bound on any artificial delay, to avoid runaway sleeps
+const MAX_DELAY_MS: u64 = 60_000;
+
+/// Generic response structure for API testing and demos
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ApiResponse {
+    pub status: String,
+    pub data: String,
+    pub path: Option<String>,
+    pub method: Option<String>,
+    /// Correlation id from the `X-Request-Id` header (or generated)
+    pub request_id: Option<String>,
+    /// Parsed query-string parameters, if the handler reports them
+    pub query: Option<BTreeMap<String, String>>,
+    /// Request headers (lowercased keys), if the handler reports them
+    pub headers: Option<BTreeMap<String, String>>,
+    /// Unix time in milliseconds when the response was produced
+    pub timestamp_ms: u64,
+}
+
+impl ApiResponse {
+    fn new(data: &str) -> Self {
+        Self {
+            status: "success".to_string(),
+            data: data.to_string(),
+            path: get_path(),
+            method: get_http_method(),
+            request_id: None,
+            query: None,
+            headers: None,
+            timestamp_ms: now_ms(),
+        }
+    }
+
+    /// Attach the caller's `X-Request-Id`, generating one if the header is absent
+    fn with_request_id(mut self) -> Self {
+        self.request_id =
+            Some(request_header("x-request-id").unwrap_or_else(|| Uuid::new_v4().to_string()));
+        self
+    }
+
+    /// Attach the parsed query string of the current request
+    fn with_query(mut self) -> Self {
+        self.query = Some(request_query());
+        self
+    }
+
+    /// Attach the headers of the current request
+    fn with_headers(mut self) -> Self {
+        self.headers = Some(request_headers());
+        self
+    }
+}
+
+/// The HTTP request currently being handled, if any
+fn current_http_request() -> Option<IncomingHttpRequest> {
+    APP_CONTEXT.with(|ctx| {
+        let ctx = ctx.borrow();
+        let message = ctx.current_message.as_ref()?;
+        match serde_json::from_slice::<HttpServerRequest>(message.body()).ok()? {
+            HttpServerRequest::Http(request) => Some(request),
+            _ => None,
+        }
+    })
+}
+
+/// Address of the process that sent the message currently being handled
+fn current_source() -> Option<String> {
+    APP_CONTEXT.with(|ctx| {
+        let ctx = ctx.borrow();
+        Some(ctx.current_message.as_ref()?.source().to_string())
+    })
+}
+
+/// Value of a header on the current HTTP request
+fn request_header(name: &str) -> Option<String> {
+    let request = current_http_request()?;
+    let value = request.headers().get(name)?.to_str().ok()?;
+    Some(value.to_string())
+}
+
+/// All headers of the current HTTP request, keyed by lowercased name
+fn request_headers() -> BTreeMap<String, String> {
+    let Some(request) = current_http_request() else {
+        return BTreeMap::new();
+    };
+    request
+        .headers()
+        .iter()
+        .filter_map(|(name, value)| {
+            let value = value.to_str().ok()?;
+            Some((name.as_str().to_lowercase(), value.to_string()))
+        })
+        .collect()
+}
+
+/// Query-string parameters of the current HTTP request; duplicate keys keep the last value
+fn request_query() -> BTreeMap<String, String> {
+    current_http_request()
+        .and_then(|request| request.url().ok())
+        .and_then(|url| url.query().map(parse_query))
+        .unwrap_or_default()
+}
+
+/// Parse `a=1&b=2` into key/value pairs, percent-decoding both sides
+fn parse_query(query: &str) -> BTreeMap<String, String> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (percent_decode(key), percent_decode(value))
+        })
+        .collect()
+}
+
+/// Decode `%XX` escapes and `+` (as space); malformed escapes are kept verbatim
+fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                let hex = |b: u8| (b as char).to_digit(16);
+                match (hex(bytes[i + 1]), hex(bytes[i + 2])) {
+                    (Some(hi), Some(lo)) => {
+                        decoded.push((hi * 16 + lo) as u8);
+                        i += 2;
+                    }
+                    _ => decoded.push(b'%'),
+                }
+            }
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+// =============================================================================
+// APPLICATION STATE
+// =============================================================================
+
+/// Collections captured before a mutation and restored together by `undo`
+#[derive(PartialEq, Clone, Debug, Default)]
+struct UndoSnapshot {
+    tasks: Vec<TodoItem>,
+    archived: Vec<TodoItem>,
+}
+
+/// Main application state containing todo tasks and WebSocket connections.
+/// Loaded through `PersistedState` so the id index is rebuilt on deserialization.
+#[derive(PartialEq, Clone, Default, Debug, Serialize, Deserialize)]
+#[serde(from = "PersistedState")]
+pub struct TodoState {
+    /// List of todo tasks
+    tasks: Vec<TodoItem>,
+    /// Completed tasks moved out of `tasks` by `archive_completed`
+    #[serde(default)]
+    archived: Vec<TodoItem>,
+    /// Ids handed out by `renumber_tasks`, accepted by `find_invalid_tasks` besides UUIDs
+    #[serde(default)]
+    renumbered_ids: Vec<String>,
+    /// Active WebSocket channel IDs (not serialized)
+    #[serde(skip)]
+    ws_channels: HashSet<u32>,
+    /// Channels that asked to receive broadcasts via the `subscribe` action (not serialized)
+    #[serde(skip)]
+    subscribers: HashSet<u32>,
+    /// Names chosen by channels via the `set_name` action (not serialized)
+    #[serde(skip)]
+    channel_names: HashMap<u32, String>,
+    /// Task id -> position in `tasks` (not serialized, rebuilt on load)
+    #[serde(skip)]
+    id_index: HashMap<String, usize>,
+    /// Unix millis when the process started (not serialized; reset to now on load)
+    #[serde(skip)]
+    started_at_ms: u64,
+    /// Number of fallback-handler hits per HTTP method (not serialized)
+    #[serde(skip)]
+    method_counts: HashMap<String, u64>,
+    /// Tasks and archive as they were before the last mutation, for single-level undo
+    #[serde(skip)]
+    last_snapshot: Option<UndoSnapshot>,
+    /// Whether mutating handlers also save explicitly as they succeed (not serialized; on after load)
+    #[serde(skip)]
+    autosave_enabled: bool,
+    /// Timestamps of recent `add_task` calls, for rate limiting (not serialized)
+    #[serde(skip)]
+    recent_adds: Vec<u64>,
+    /// Timestamped adds, toggles and deletions for this process run (not serialized)
+    #[serde(skip)]
+    events: Vec<(u64, TaskEvent)>,
+    /// Calls made to `/flaky`, driving its fail-fail-succeed cycle (not serialized)
+    #[serde(skip)]
+    flaky_counter: u32,
+    /// HTTP handler invocations since start or the last reset (not serialized).
+    /// A `Cell` so read-only handlers can count themselves too.
+    #[serde(skip)]
+    call_count: Cell<u64>,
+}
+
+/// The serialized fields of `TodoState`; everything else starts fresh on load
+#[derive(Deserialize)]
+struct PersistedState {
+    tasks: Vec<TodoItem>,
+    #[serde(default)]
+    archived: Vec<TodoItem>,
+    #[serde(default)]
+    renumbered_ids: Vec<String>,
+}
+
+impl From<PersistedState> for TodoState {
+    fn from(persisted: PersistedState) -> Self {
+        let mut state = TodoState {
+            tasks: persisted.tasks,
+            archived: persisted.archived,
+            renumbered_ids: persisted.renumbered_ids,
+            started_at_ms: now_ms(),
+            autosave_enabled: true,
+            ..Default::default()
+        };
+        state.reindex();
+        state
+    }
+}
+
+impl TodoState {
+    /// Milliseconds since this process run started
+    fn uptime_ms(&self) -> u64 {
+        now_ms().saturating_sub(self.started_at_ms)
+    }
+
+    /// Count an HTTP handler invocation; every `#[http]` handler calls this first
+    fn count_call(&self) {
+        self.call_count.set(self.call_count.get() + 1);
+    }
+
+    /// Count a fallback-handler hit for the current HTTP method
+    fn record_method(&mut self) {
+        let method = get_http_method().unwrap_or_default();
+        *self.method_counts.entry(method).or_insert(0) += 1;
+    }
+
+    /// Capture the tasks and archive before a mutation. Handlers pass it to
+    /// `remember` only once the mutation has succeeded, so rejected calls and
+    /// no-ops leave the previous undo point intact.
+    fn snapshot(&self) -> UndoSnapshot {
+        UndoSnapshot {
+            tasks: self.tasks.clone(),
+            archived: self.archived.clone(),
+        }
+    }
+
+    /// Make `snapshot` the state restored by the next `undo`
+    fn remember(&mut self, snapshot: UndoSnapshot) {
+        self.last_snapshot = Some(snapshot);
+    }
+
+    /// Persist the state now
+    fn save(&self) -> Result<(), TodoError> {
+        let bytes = rmp_serde::to_vec(self)
+            .map_err(|e| TodoError::Internal(format!("Failed to serialize state: {}", e)))?;
+        set_state(&bytes);
+        Ok(())
+    }
+
+    /// Save right away if autosave is on; mutating handlers call this once they
+    /// succeed. The framework still saves after every message regardless.
+    fn autosave(&self) {
+        if self.autosave_enabled {
+            if let Err(e) = self.save() {
+                kiprintln!("Autosave failed: {}", e);
+            }
+        }
+    }
+
+    /// Record an add, rejecting it if the window already holds `RATE_LIMIT_MAX_ADDS`
+    fn check_rate_limit(&mut self) -> Result<(), TodoError> {
+        let now = now_ms();
+        self.recent_adds
+            .retain(|&ts| now.saturating_sub(ts) < RATE_LIMIT_WINDOW_MS);
+        if self.recent_adds.len() >= RATE_LIMIT_MAX_ADDS {
+            return Err(TodoError::Conflict("rate limited".to_string()));
+        }
+        self.recent_adds.push(now);
+        Ok(())
+    }
+
+    /// Rebuild the id index from scratch; call after any reordering or removal
+    fn reindex(&mut self) {
+        self.id_index = self
+            .tasks
+            .iter()
+            .enumerate()
+            .map(|(i, t)| (t.id.clone(), i))
+            .collect();
+    }
+
+    /// Position of a task in `tasks`. A stale index entry is logged and
+    /// answered with a scan, so the bug shows up without breaking lookups.
+    fn position(&self, task_id: &str) -> Option<usize> {
+        match self.id_index.get(task_id) {
+            Some(&i) if self.tasks.get(i).is_some_and(|t| t.id == task_id) => Some(i),
+            None if self.id_index.len() == self.tasks.len() => None,
+            _ => {
+                kiprintln!("Stale id index while looking up task {}", task_id);
+                self.tasks.iter().position(|t| t.id == task_id)
+            }
+        }
+    }
+
+    /// Look up a task by id
+    fn task(&self, task_id: &str) -> Result<&TodoItem, TodoError> {
+        self.position(task_id)
+            .map(|i| &self.tasks[i])
+            .ok_or_else(|| TodoError::task_not_found(task_id))
+    }
+
+    /// Look up a task by id for mutation
+    fn task_mut(&mut self, task_id: &str) -> Result<&mut TodoItem, TodoError> {
+        match self.position(task_id) {
+            Some(i) => Ok(&mut self.tasks[i]),
+            None => Err(TodoError::task_not_found(task_id)),
+        }
+    }
+
+    /// Push a JSON message to every subscribed WebSocket channel
+    fn broadcast(&self, value: serde_json::Value) {
+        for &channel_id in &self.subscribers {
+            send_ws_json(channel_id, &value);
+        }
+    }
+
+    /// Build a `{"type":kind,"task":...,"tasks":...}` update for a single task,
+    /// shared by the HTTP and WebSocket paths so both emit the same frame shape
+    fn task_frame(&self, kind: &str, task: &TodoItem) -> serde_json::Value {
+        serde_json::json!({
+            "type": kind,
+            "task": task,
+            "tasks": self.tasks
+        })
+    }
+
+    /// Broadcast a single-task update triggered over HTTP
+    fn broadcast_task(&self, kind: &str, task: &TodoItem) {
+        self.broadcast(self.task_frame(kind, task));
+    }
+
+    /// Broadcast the whole list after a change spanning several tasks
+    fn broadcast_overview(&self) {
+        self.broadcast(serde_json::json!({
+            "type": "tasks_overview",
+            "tasks": self.tasks
+        }));
+    }
+
+    /// Broadcast a frame triggered by a WebSocket client, tagged with its
+    /// `set_name` name as `"from"` when it has one. The sender always gets the
+    /// frame, even if it never subscribed, so its request is not left hanging.
+    fn broadcast_from(&self, channel_id: u32, mut value: serde_json::Value) {
+        if let Some(name) = self.channel_names.get(&channel_id) {
+            value["from"] = serde_json::json!(name);
+        }
+        if !self.subscribers.contains(&channel_id) {
+            send_ws_json(channel_id, &value);
+        }
+        self.broadcast(value);
+    }
+
+    /// Remove a task and, recursively, all of its subtasks. Returns the removed
+    /// ids (the task itself first), or `None` if the task does not exist.
+    fn remove_task(&mut self, task_id: &str) -> Option<Vec<String>> {
+        self.position(task_id)?;
+
+        let mut doomed: HashSet<String> = HashSet::from([task_id.to_string()]);
+        let mut deleted_ids = vec![task_id.to_string()];
+        let mut cursor = 0;
+        while cursor < deleted_ids.len() {
+            let parent = deleted_ids[cursor].clone();
+            for task in &self.tasks {
+                if task.parent_id.as_deref() == Some(parent.as_str())
+                    && doomed.insert(task.id.clone())
+                {
+                    deleted_ids.push(task.id.clone());
+                }
+            }
+            cursor += 1;
+        }
+
+        self.tasks.retain(|t| !doomed.contains(&t.id));
+        self.reindex();
+        for id in &deleted_ids {
+            self.record_event(TaskEvent::Deleted { id: id.clone() });
+        }
+        Some(deleted_ids)
+    }
+
+    /// Re-parent tasks whose parent is in `removed` onto their nearest ancestor
+    /// still in the list, or make them top-level. Re-parented tasks are touched.
+    fn reparent_orphans(&mut self, removed: &[TodoItem]) {
+        let removed_parent: HashMap<&str, Option<&String>> = removed
+            .iter()
+            .map(|t| (t.id.as_str(), t.parent_id.as_ref()))
+            .collect();
+        for task in &mut self.tasks {
+            let mut parent = task.parent_id.as_ref();
+            let mut visited = HashSet::new();
+            // Walk up past removed ancestors; `visited` guards against parent cycles
+            while let Some(&grandparent) = parent
+                .filter(|p| visited.insert(p.as_str()))
+                .and_then(|p| removed_parent.get(p.as_str()))
+            {
+                parent = grandparent;
+            }
+            if parent.is_some_and(|p| removed_parent.contains_key(p.as_str())) {
+                parent = None;
+            }
+            let parent = parent.cloned();
+            if parent != task.parent_id {
+                task.parent_id = parent;
+                task.touch();
+            }
+        }
+    }
+
+    /// Handle a JSON action received over WebSocket; errors are sent back to the caller
+    fn handle_ws_action(
+        &mut self,
+        channel_id: u32,
+        json: &serde_json::Value,
+    ) -> Result<(), TodoError> {
+        let action = ws_str_field(json, "action")?;
+        match action {
+            "get_tasks" => {
+                // Send current tasks to the requesting client
+                send_ws_json(
+                    channel_id,
+                    &serde_json::json!({
+                        "type": "tasks_overview",
+                        "tasks": self.tasks
+                    }),
+                );
+            }
+            "get_tasks_filtered" => {
+                let completed = match json.get("completed") {
+                    None | Some(serde_json::Value::Null) => None,
+                    Some(value) => Some(value.as_bool().ok_or_else(|| {
+                        TodoError::Validation("field completed must be a boolean".to_string())
+                    })?),
+                };
+                send_ws_json(
+                    channel_id,
+                    &serde_json::json!({
+                        "type": "tasks_overview",
+                        "tasks": self.filtered(completed)
+                    }),
+                );
+            }
+            "subscribe" => {
+                self.subscribers.insert(channel_id);
+                send_ws_json(channel_id, &serde_json::json!({ "type": "subscribed" }));
+            }
+            "unsubscribe" => {
+                self.subscribers.remove(&channel_id);
+                send_ws_json(channel_id, &serde_json::json!({ "type": "unsubscribed" }));
+            }
+            "set_name" => {
+                let name = ws_str_field(json, "name")?;
+                if name.trim().is_empty() {
+                    return Err(TodoError::Validation("Name cannot be empty".to_string()));
+                }
+                self.channel_names.insert(channel_id, name.to_string());
+                send_ws_json(
+                    channel_id,
+                    &serde_json::json!({ "type": "name_set", "name": name }),
+                );
+            }
+            "add_task" => {
+                let snapshot = self.snapshot();
+                let text = ws_str_field(json, "text")?;
+                if text.trim().is_empty() {
+                    return Err(TodoError::empty_text());
+                }
+                self.ensure_capacity()?;
+                let new_task = TodoItem::new(text.to_string());
+                self.push_task(new_task.clone());
+
+                // Broadcast the update to subscribers and the sender
+                self.broadcast_from(channel_id, self.task_frame("task_added", &new_task));
+                self.remember(snapshot);
+                self.autosave();
+            }
+            "toggle_task" => {
+                let snapshot = self.snapshot();
+                let id = ws_str_field(json, "id")?;
+                let task = self.task_mut(id)?;
+                task.completed = !task.completed;
+                task.touch();
+
+                // Broadcast the update to subscribers and the sender
+                let task = task.clone();
+                self.record_toggle(&task);
+                self.broadcast_from(channel_id, self.task_frame("task_toggled", &task));
+                self.remember(snapshot);
+                self.autosave();
+            }
+            "delete_task" => {
+                let snapshot = self.snapshot();
+                let id = ws_str_field(json, "id")?;
+                let deleted_ids = self
+                    .remove_task(id)
+                    .ok_or_else(|| TodoError::task_not_found(id))?;
+
+                // Broadcast the update to subscribers and the sender
+                self.broadcast_from(
+                    channel_id,
+                    serde_json::json!({
+                        "type": "task_deleted",
+                        "id": id,
+                        "deleted_ids": deleted_ids,
+                        "tasks": self.tasks
+                    }),
+                );
+                self.remember(snapshot);
+                self.autosave();
+            }
+            _ => return Err(TodoError::Validation(format!("unknown action: {}", action))),
+        }
+        Ok(())
+    }
+
+    /// Error if the task list is already at `MAX_TASKS`
+    fn ensure_capacity(&self) -> Result<(), TodoError> {
+        ensure_fits(self.tasks.len() + 1)
+    }
+
+    /// Append a task, keeping the id index in sync
+    fn push_task(&mut self, task: TodoItem) {
+        self.record_event(TaskEvent::Added {
+            id: task.id.clone(),
+        });
+        self.id_index.insert(task.id.clone(), self.tasks.len());
+        self.tasks.push(task);
+    }
+
+    /// Append an event to the log, stamped with the current time
+    fn record_event(&mut self, event: TaskEvent) {
+        self.events.push((now_ms(), event));
+    }
+
+    /// Log a toggle carrying the task's new completion status
+    fn record_toggle(&mut self, task: &TodoItem) {
+        self.record_event(TaskEvent::Toggled {
+            id: task.id.clone(),
+            completed: task.completed,
+        });
+    }
+
+    /// Log how the list changed since `before`: deletions for ids that are gone,
+    /// adds for new ids and toggles for tasks whose completion flipped
+    fn record_changes(&mut self, before: &[TodoItem]) {
+        let previous: HashMap<&str, bool> = before
+            .iter()
+            .map(|t| (t.id.as_str(), t.completed))
+            .collect();
+        let mut events: Vec<TaskEvent> = before
+            .iter()
+            .filter(|t| self.position(&t.id).is_none())
+            .map(|t| TaskEvent::Deleted { id: t.id.clone() })
+            .collect();
+        for task in &self.tasks {
+            match previous.get(task.id.as_str()) {
+                None => events.push(TaskEvent::Added {
+                    id: task.id.clone(),
+                }),
+                Some(&completed) if completed != task.completed => {
+                    events.push(TaskEvent::Toggled {
+                        id: task.id.clone(),
+                        completed: task.completed,
+                    })
+                }
+                _ => {}
+            }
+        }
+        for event in events {
+            self.record_event(event);
+        }
+    }
+
+    /// Build the subtree under `index`, skipping any task already placed so a
+    /// malformed parent cycle can't recurse forever
+    fn task_node<'a>(
+        &'a self,
+        index: usize,
+        children: &HashMap<&str, Vec<usize>>,
+        visited: &mut HashSet<&'a str>,
+    ) -> TaskNode {
+        let item = &self.tasks[index];
+        visited.insert(&item.id);
+        let mut node = TaskNode {
+            item: item.clone(),
+            children: Vec::new(),
+        };
+        for &child in children.get(item.id.as_str()).into_iter().flatten() {
+            if !visited.contains(self.tasks[child].id.as_str()) {
+                node.children.push(self.task_node(child, children, visited));
+            }
+        }
+        node
+    }
+
+    /// Tasks with the given completion status (`None` matches all)
+    fn filtered(&self, completed: Option<bool>) -> Vec<TodoItem> {
+        self.tasks
+            .iter()
+            .filter(|t| completed.is_none_or(|c| t.completed == c))
+            .cloned()
+            .collect()
+    }
+
+    /// Slice of tasks for the given page, clamped to the available length
+    fn page(&self, offset: usize, limit: usize) -> Vec<TodoItem> {
+        let start = offset.min(self.tasks.len());
+        let end = offset.saturating_add(limit).min(self.tasks.len());
+        self.tasks[start..end].to_vec()
+    }
+}
+
+/// Error if a list of `count` tasks would exceed `MAX_TASKS`
+fn ensure_fits(count: usize) -> Result<(), TodoError> {
+    if count > MAX_TASKS {
+        return Err(TodoError::Conflict("Task limit reached".to_string()));
+    }
+    Ok(())
+}
+
+/// Reject a task list containing empty texts or duplicate ids
+fn validate_task_list(tasks: &[TodoItem]) -> Result<(), TodoError> {
+    let mut seen = HashSet::new();
+    for task in tasks {
+        if task.text.trim().is_empty() {
+            return Err(TodoError::Validation(format!(
+                "Task with id '{}' has empty text",
+                task.id
+            )));
+        }
+        if !seen.insert(task.id.as_str()) {
+            return Err(TodoError::Validation(format!(
+                "Duplicate task id '{}'",
+                task.id
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Levenshtein edit distance between two strings, counted in chars
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut curr = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = prev[j] + usize::from(ca != *cb);
+            curr[j + 1] = substitution.min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        prev = curr;
+    }
+    prev[b.len()]
+}
+
+/// Quote a CSV field if it contains a comma, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Split CSV text into records, honoring `csv_field` quoting so a quoted field
+/// may span line breaks. Each record is paired with the line it starts on; a
+/// stray or unterminated quote is reported as `Err(line)`.
+fn parse_csv(input: &str) -> Result<Vec<(usize, Vec<String>)>, usize> {
+    let mut records = Vec::new();
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut chars = input.chars().peekable();
+    let mut quoted = false;
+    let mut line = 1;
+    let mut start = 1;
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' if quoted => quoted = false,
+            '"' if field.is_empty() => quoted = true,
+            '"' => return Err(line),
+            '\n' if quoted => {
+                field.push(c);
+                line += 1;
+            }
+            '\r' if !quoted && chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                fields.push(std::mem::take(&mut field));
+                records.push((start, std::mem::take(&mut fields)));
+                line += 1;
+                start = line;
+            }
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    if quoted {
+        return Err(start);
+    }
+    fields.push(field);
+    records.push((start, fields));
+    Ok(records)
+}
+
+/// Push a JSON message to a single WebSocket channel
+fn send_ws_json(channel_id: u32, value: &serde_json::Value) {
+    let blob = LazyLoadBlob {
+        mime: Some("application/json".to_string()),
+        bytes: value.to_string().into_bytes(),
+    };
+    send_ws_push(channel_id, WsMessageType::Text, blob);
+}
+
+/// Read a required string field from a WebSocket JSON message
+fn ws_str_field<'a>(json: &'a serde_json::Value, name: &str) -> Result<&'a str, TodoError> {
+    json.get(name)
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| TodoError::Validation(format!("missing field: {}", name)))
+}
+
+/// Push a `{"type":"error"}` frame to a single WebSocket channel
+fn send_ws_error(channel_id: u32, message: &str) {
+    send_ws_json(
+        channel_id,
+        &serde_json::json!({
+            "type": "error",
+            "message": message
+        }),
+    );
+}
+
+// =============================================================================
+// HYPERPROCESS CONFIGURATION
+// =============================================================================
+
+#[hyperprocess(
+    name = "todo",
+    ui = Some(HttpBindingConfig::default()),
+    endpoints = vec![
+        Binding::Http {
+            path: "/health",
+            config: HttpBindingConfig::new(false, false, false, None),
+        },
+        Binding::Http {
+            path: "/ping",
+            config: HttpBindingConfig::new(false, false, false, None),
+        },
+        Binding::Http {
+            path: "/version",
+            config: HttpBindingConfig::new(false, false, false, None),
+        },
+        Binding::Ws {
+            path: "/ws",
+            config: WsBindingConfig::new(false, false, false),
+        },
+        // Demo/testing endpoints
+        Binding::Http {
+            path: "/users",
+            config: HttpBindingConfig::new(false, false, false, None),
+        },
+        Binding::Http {
+            path: "/posts",
+            config: HttpBindingConfig::new(false, false, false, None),
+        },
+        Binding::Http {
+            path: "/api",
+            config: HttpBindingConfig::new(false, false, false, None),
+        },
+        Binding::Http {
+            path: "/admin",
+            config: HttpBindingConfig::new(false, false, false, None),
+        },
+        Binding::Http {
+            path: "/test",
+            config: HttpBindingConfig::new(false, false, false, None),
+        },
+        Binding::Http {
+            path: "/api/unknown",
+            config: HttpBindingConfig::new(false, false, false, None),
+        },
+        Binding::Http {
+            path: "/admin/dashboard", 
+            config: HttpBindingConfig::new(false, false, false, None),
+        },
+        Binding::Http {
+            path: "/test/something",
+            config: HttpBindingConfig::new(false, false, false, None),
+        },
+        Binding::Http {
+            path: "/api/upload",
+            config: HttpBindingConfig::new(false, false, false, None),
+        },
+        Binding::Http {
+            path: "/other/endpoint",
+            config: HttpBindingConfig::new(false, false, false, None),
+        },
+        Binding::Http {
+            path: "/anything",
+            config: HttpBindingConfig::new(false, false, false, None),
+        },
+        Binding::Http {
+            path: "/whatever",
+            config: HttpBindingConfig::new(false, false, false, None),
+        },
+        Binding::Http {
+            path: "/some/path",
+            config: HttpBindingConfig::new(false, false, false, None),
+        },
+        Binding::Http {
+            path: "/api/data",
+            config: HttpBindingConfig::new(false, false, false, None),
+        },
+        Binding::Http {
+            path: "/echo-delay",
+            config: HttpBindingConfig::new(false, false, false, None),
+        },
+        Binding::Http {
+            path: "/status",
+            config: HttpBindingConfig::new(false, false, false, None),
+        },
+        Binding::Http {
+            path: "/api/echo",
+            config: HttpBindingConfig::new(false, false, false, None),
+        },
+        Binding::Http {
+            path: "/echo-raw",
+            config: HttpBindingConfig::new(false, false, false, None),
+        },
+        Binding::Http {
+            path: "/compute",
+            config: HttpBindingConfig::new(false, false, false, None),
+        },
+        Binding::Http {
+            path: "/slow-steps",
+            config: HttpBindingConfig::new(false, false, false, None),
+        },
+        Binding::Http {
+            path: "/whoami",
+            config: HttpBindingConfig::new(false, false, false, None),
+        },
+        Binding::Http {
+            path: "/delay-random",
+            config: HttpBindingConfig::new(false, false, false, None),
+        },
+        Binding::Http {
+            path: "/time",
+            config: HttpBindingConfig::new(false, false, false, None),
+        },
+        Binding::Http {
+            path: "/flaky",
+            config: HttpBindingConfig::new(false, false, false, None),
+        },
+        Binding::Http {
+            path: "/bulk-echo",
+            config: HttpBindingConfig::new(false, false, false, None),
+        },
+    ],
+    save_config = SaveOptions::EveryMessage,
+    wit_world = "todo-template-dot-os-v0"
+)]
+
+// =============================================================================
+// CORE TODO APPLICATION IMPLEMENTATION
+// =============================================================================
+
+impl TodoState {
+    /// Initialize the application state
+    #[init]
+    async fn initialize(&mut self) {
+        kiprintln!("Initializing todo list state");
+        self.tasks = Vec::new();
+        self.ws_channels = HashSet::new();
+        self.reindex();
+        self.started_at_ms = now_ms();
+        self.autosave_enabled = true;
+    }
+
+    // -------------------------------------------------------------------------
+    // CORE TODO FUNCTIONALITY
+    // -------------------------------------------------------------------------
+
+    /// Add a new todo task, optionally with a priority (defaults to Medium)
+    /// and as a subtask of an existing `parent_id`. Rejected with "rate limited"
+    /// once `RATE_LIMIT_MAX_ADDS` calls land within `RATE_LIMIT_WINDOW_MS`.
+    #[http]
+    async fn add_task(
+        &mut self,
+        text: String,
+        priority: Option<Priority>,
+        parent_id: Option<String>,
+    ) -> Result<TodoItem, TodoError> {
+        self.count_call();
+        let snapshot = self.snapshot();
+        if text.trim().is_empty() {
+            return Err(TodoError::empty_text());
+        }
+        self.ensure_capacity()?;
+        if let Some(parent_id) = &parent_id {
+            self.task(parent_id).map_err(|_| {
+                TodoError::NotFound(format!("Parent task with id '{}' not found", parent_id))
+            })?;
+        }
+        // Only requests that would otherwise succeed count toward the limit
+        self.check_rate_limit()?;
+
+        let mut new_task = TodoItem::new(text);
+        if let Some(priority) = priority {
+            new_task.priority = priority;
+        }
+        new_task.parent_id = parent_id;
+
+        self.push_task(new_task.clone());
+        kiprintln!("Added task: {:?}", new_task);
+        self.broadcast_task("task_added", &new_task);
+        self.remember(snapshot);
+        self.autosave();
+
+        Ok(new_task)
+    }
+
+    /// Add a task after normalizing its text: surrounding whitespace is trimmed,
+    /// internal runs collapse to one space, and over-long text is rejected
+    #[http]
+    async fn add_task_strict(&mut self, text: String) -> Result<TodoItem, TodoError> {
+        self.count_call();
+        let snapshot = self.snapshot();
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if text.is_empty() {
+            return Err(TodoError::empty_text());
+        }
+        if text.chars().count() > MAX_TASK_TEXT_LEN {
+            return Err(TodoError::Validation("Task text too long".to_string()));
+        }
+        self.ensure_capacity()?;
+
+        let new_task = TodoItem::new(text);
+        self.push_task(new_task.clone());
+        kiprintln!("Added task: {:?}", new_task);
+        self.broadcast_task("task_added", &new_task);
+        self.remember(snapshot);
+        self.autosave();
+
+        Ok(new_task)
+    }
+
+    /// Check task text without adding anything: it must be non-blank and at
+    /// most `MAX_TASK_TEXT_LEN` characters once trimmed
+    #[http]
+    fn validate_task_text(&self, text: String) -> Result<(), TodoError> {
+        self.count_call();
+        let text = text.trim();
+        if text.is_empty() {
+            return Err(TodoError::empty_text());
+        }
+        if text.chars().count() > MAX_TASK_TEXT_LEN {
+            return Err(TodoError::Validation("Task text too long".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Add several tasks at once, skipping empty texts. Adds as many as fit
+    /// under `MAX_TASKS` and reports the rest as skipped.
+    #[http]
+    async fn add_tasks(&mut self, texts: Vec<String>) -> Result<BulkAddResult, TodoError> {
+        self.count_call();
+        let snapshot = self.snapshot();
+        let mut new_tasks: Vec<TodoItem> = texts
+            .into_iter()
+            .filter(|text| !text.trim().is_empty())
+            .map(TodoItem::new)
+            .collect();
+
+        if new_tasks.is_empty() {
+            return Err(TodoError::Validation("No valid tasks to add".to_string()));
+        }
+        self.ensure_capacity()?;
+
+        let room = MAX_TASKS - self.tasks.len();
+        let skipped = new_tasks.len().saturating_sub(room);
+        new_tasks.truncate(room);
+
+        for task in &new_tasks {
+            self.push_task(task.clone());
+        }
+        kiprintln!("Added {} tasks, skipped {}", new_tasks.len(), skipped);
+        self.broadcast(serde_json::json!({
+            "type": "tasks_added",
+            "added": new_tasks,
+            "tasks": self.tasks
+        }));
+        self.remember(snapshot);
+        self.autosave();
+
+        Ok(BulkAddResult {
+            added: new_tasks,
+            skipped,
+        })
+    }
+
+    /// Copy an existing task under a fresh id and append it
+    #[http]
+    async fn duplicate_task(&mut self, task_id: String) -> Result<TodoItem, TodoError> {
+        self.count_call();
+        let snapshot = self.snapshot();
+        self.ensure_capacity()?;
+        let mut new_task = self.task(&task_id)?.clone();
+        new_task.id = Uuid::new_v4().to_string();
+        new_task.version = 0;
+        new_task.created_at_ms = now_ms();
+        new_task.updated_at_ms = new_task.created_at_ms;
+
+        self.push_task(new_task.clone());
+        kiprintln!("Duplicated task {} as {}", task_id, new_task.id);
+        self.broadcast_task("task_added", &new_task);
+        self.remember(snapshot);
+        self.autosave();
+
+        Ok(new_task)
+    }
+
+    /// Get all todo tasks
+    #[http]
+    async fn get_tasks(&self, request: String) -> Result<Vec<TodoItem>, TodoError> {
+        self.count_call();
+        kiprintln!("Request: {:?}", request);
+        kiprintln!("Fetching tasks");
+        Ok(self.tasks.clone())
+    }
+
+    /// Get all tasks in reverse list order (newest insertion first)
+    #[http]
+    fn get_tasks_reversed(&self) -> Vec<TodoItem> {
+        self.count_call();
+        self.tasks.iter().rev().cloned().collect()
+    }
+
+    /// Get just the ids of all tasks, in list order
+    #[http]
+    fn get_task_ids(&self) -> Vec<String> {
+        self.count_call();
+        self.tasks.iter().map(|t| t.id.clone()).collect()
+    }
+
+    /// Get a single todo task by id
+    #[http]
+    async fn get_task(&self, task_id: String) -> Result<TodoItem, TodoError> {
+        self.count_call();
+        self.task(&task_id).cloned()
+    }
+
+    /// Get the direct subtasks of a task
+    #[http]
+    async fn get_subtasks(&self, parent_id: String) -> Result<Vec<TodoItem>, TodoError> {
+        self.count_call();
+        Ok(self
+            .tasks
+            .iter()
+            .filter(|t| t.parent_id.as_deref() == Some(parent_id.as_str()))
+            .cloned()
+            .collect())
+    }
+
+    /// Get the tasks as a forest following `parent_id`, in list order. Tasks whose
+    /// parent no longer exists are treated as roots, and each parent cycle is
+    /// broken by promoting one of its members to a root, so every task appears
+    /// exactly once.
+    #[http]
+    fn get_task_tree(&self) -> Vec<TaskNode> {
+        self.count_call();
+        let mut children: HashMap<&str, Vec<usize>> = HashMap::new();
+        let mut roots = Vec::new();
+        for (i, task) in self.tasks.iter().enumerate() {
+            match task.parent_id.as_deref() {
+                Some(parent) if self.position(parent).is_some() => {
+                    children.entry(parent).or_default().push(i)
+                }
+                _ => roots.push(i),
+            }
+        }
+
+        let mut visited = HashSet::new();
+        let mut forest: Vec<TaskNode> = roots
+            .into_iter()
+            .map(|i| self.task_node(i, &children, &mut visited))
+            .collect();
+
+        // Anything not reached yet hangs off a parent cycle: walk up from it to
+        // the first repeated task, which is on the cycle, and make that a root
+        for (i, task) in self.tasks.iter().enumerate() {
+            if visited.contains(task.id.as_str()) {
+                continue;
+            }
+            let mut seen = HashSet::new();
+            let mut member = i;
+            while seen.insert(member) {
+                match self.tasks[member]
+                    .parent_id
+                    .as_deref()
+                    .and_then(|parent| self.position(parent))
+                {
+                    Some(parent) => member = parent,
+                    None => break,
+                }
+            }
+            forest.push(self.task_node(member, &children, &mut visited));
+        }
+        forest
+    }
+
+    /// Get tasks created strictly after `since_ms`, oldest first
+    #[http]
+    async fn get_tasks_since(&self, since_ms: u64) -> Result<Vec<TodoItem>, TodoError> {
+        self.count_call();
+        let mut tasks: Vec<TodoItem> = self
+            .tasks
+            .iter()
+            .filter(|t| t.created_at_ms > since_ms)
+            .cloned()
+            .collect();
+        tasks.sort_by_key(|t| t.created_at_ms);
+        Ok(tasks)
+    }
+
+    /// Get up to `n` tasks, newest first by creation time
+    #[http]
+    fn get_latest_tasks(&self, n: usize) -> Vec<TodoItem> {
+        self.count_call();
+        let mut tasks = self.tasks.clone();
+        tasks.sort_by_key(|t| std::cmp::Reverse(t.created_at_ms));
+        tasks.truncate(n);
+        tasks
+    }
+
+    /// Get the oldest pending task by creation time (list order breaks ties)
+    #[http]
+    fn next_task(&self) -> Result<TodoItem, TodoError> {
+        self.count_call();
+        self.tasks
+            .iter()
+            .filter(|t| !t.completed)
+            .min_by_key(|t| t.created_at_ms)
+            .cloned()
+            .ok_or_else(|| TodoError::NotFound("no pending tasks".to_string()))
+    }
+
+    /// Get tasks filtered by completion status (`None` returns all tasks)
+    #[http]
+    async fn get_tasks_filtered(
+        &self,
+        completed: Option<bool>,
+    ) -> Result<Vec<TodoItem>, TodoError> {
+        self.count_call();
+        Ok(self.filtered(completed))
+    }
+
+    /// Get pending and completed tasks in one response
+    #[http]
+    fn get_tasks_grouped(&self) -> GroupedTasks {
+        self.count_call();
+        let (completed, pending) = self.tasks.iter().cloned().partition(|t| t.completed);
+        GroupedTasks { pending, completed }
+    }
+
+    /// Get a page of tasks; out-of-range offsets yield an empty list
+    #[http]
+    async fn get_tasks_paged(
+        &self,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<TodoItem>, TodoError> {
+        self.count_call();
+        Ok(self.page(offset, limit))
+    }
+
+    /// Get a page of tasks wrapped with the total task count
+    #[http]
+    async fn get_tasks_page(&self, offset: usize, limit: usize) -> Result<PagedTasks, TodoError> {
+        self.count_call();
+        Ok(PagedTasks {
+            items: self.page(offset, limit),
+            total: self.tasks.len(),
+            offset,
+            limit,
+        })
+    }
+
+    /// Case-insensitive substring search over task text (empty query matches all)
+    #[http]
+    async fn search_tasks(&self, query: String) -> Result<Vec<TodoItem>, TodoError> {
+        self.count_call();
+        let query = query.to_lowercase();
+        Ok(self
+            .tasks
+            .iter()
+            .filter(|t| t.text.to_lowercase().contains(&query))
+            .cloned()
+            .collect())
+    }
+
+    /// Case-insensitive typo-tolerant search: matches tasks whose whole text, or
+    /// any single word of it, is within `max_distance` edits of the query
+    #[http]
+    async fn fuzzy_search(
+        &self,
+        query: String,
+        max_distance: usize,
+    ) -> Result<Vec<TodoItem>, TodoError> {
+        self.count_call();
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return Err(TodoError::Validation("Query cannot be empty".to_string()));
+        }
+        Ok(self
+            .tasks
+            .iter()
+            .filter(|t| {
+                let text = t.text.to_lowercase();
+                levenshtein(&text, &query) <= max_distance
+                    || text
+                        .split_whitespace()
+                        .any(|word| levenshtein(word, &query) <= max_distance)
+            })
+            .cloned()
+            .collect())
+    }
+
+    /// Get tasks carrying the given tag (exact, case-sensitive match)
+    #[http]
+    async fn get_tasks_by_tag(&self, tag: String) -> Result<Vec<TodoItem>, TodoError> {
+        self.count_call();
+        if tag.is_empty() {
+            return Err(TodoError::Validation("Tag cannot be empty".to_string()));
+        }
+        Ok(self
+            .tasks
+            .iter()
+            .filter(|t| t.tags.contains(&tag))
+            .cloned()
+            .collect())
+    }
+
+    /// Get tasks carrying every tag (`match_all`) or at least one of them
+    #[http]
+    async fn get_tasks_by_tags(
+        &self,
+        tags: Vec<String>,
+        match_all: bool,
+    ) -> Result<Vec<TodoItem>, TodoError> {
+        self.count_call();
+        if tags.is_empty() {
+            return Err(TodoError::Validation(
+                "At least one tag is required".to_string(),
+            ));
+        }
+        Ok(self
+            .tasks
+            .iter()
+            .filter(|t| {
+                if match_all {
+                    tags.iter().all(|tag| t.tags.contains(tag))
+                } else {
+                    tags.iter().any(|tag| t.tags.contains(tag))
+                }
+            })
+            .cloned()
+            .collect())
+    }
+
+    /// Get a sorted copy of the tasks; stored order is left untouched
+    #[http]
+    async fn get_tasks_sorted(&self, sort_by: SortKey) -> Result<Vec<TodoItem>, TodoError> {
+        self.count_call();
+        let mut tasks = self.tasks.clone();
+        match sort_by {
+            SortKey::Text => tasks.sort_by_cached_key(|t| t.text.to_lowercase()),
+            SortKey::Completed => tasks.sort_by_key(|t| t.completed),
+            SortKey::Id => tasks.sort_by(|a, b| a.id.cmp(&b.id)),
+        }
+        Ok(tasks)
+    }
+
+    /// Count total, completed and pending tasks
+    #[http]
+    fn count_tasks(&self) -> TaskCounts {
+        self.count_call();
+        let completed = self.tasks.iter().filter(|t| t.completed).count();
+        TaskCounts {
+            total: self.tasks.len(),
+            completed,
+            pending: self.tasks.len() - completed,
+        }
+    }
+
+    /// Whether at least one task matches every condition in `filter`
+    #[http]
+    fn any_task(&self, filter: TaskFilter) -> bool {
+        self.count_call();
+        self.tasks.iter().any(|t| filter.matches(t))
+    }
+
+    /// Number of tasks matching every condition in `filter`
+    #[http]
+    fn count_matching(&self, filter: TaskFilter) -> usize {
+        self.count_call();
+        self.tasks.iter().filter(|t| filter.matches(t)).count()
+    }
+
+    /// Fraction of tasks completed, 0.0 when the list is empty
+    #[http]
+    fn completion_ratio(&self) -> f64 {
+        self.count_call();
+        if self.tasks.is_empty() {
+            return 0.0;
+        }
+        let completed = self.tasks.iter().filter(|t| t.completed).count();
+        completed as f64 / self.tasks.len() as f64
+    }
+
+    /// Whitespace-separated word counts across all task text
+    #[http]
+    fn word_stats(&self) -> WordStats {
+        self.count_call();
+        let counts: Vec<usize> = self
+            .tasks
+            .iter()
+            .map(|t| t.text.split_whitespace().count())
+            .collect();
+        let total_words = counts.iter().sum();
+        let avg_words = if counts.is_empty() {
+            0.0
+        } else {
+            total_words as f64 / counts.len() as f64
+        };
+        // max_by_key keeps the last maximum, so reverse to prefer the earliest task
+        let longest_task_id = counts
+            .iter()
+            .enumerate()
+            .rev()
+            .max_by_key(|(_, &count)| count)
+            .map(|(i, _)| self.tasks[i].id.clone());
+        WordStats {
+            total_tasks: self.tasks.len(),
+            total_words,
+            avg_words,
+            longest_task_id,
+        }
+    }
+
+    /// Toggle a todo task's completion status.
+    /// Rejects the change if `expected_version` does not match the stored version
+    #[http]
+    async fn toggle_task(
+        &mut self,
+        task_id: String,
+        expected_version: u64,
+    ) -> Result<TodoItem, TodoError> {
+        self.count_call();
+        let snapshot = self.snapshot();
+        kiprintln!("Toggling task: {}", task_id);
+
+        let task = self.task_mut(&task_id)?;
+        if task.version != expected_version {
+            return Err(TodoError::Conflict("version conflict".to_string()));
+        }
+        task.completed = !task.completed;
+        task.touch();
+        kiprintln!("Task toggled: {:?}", task);
+        let task = task.clone();
+        self.record_toggle(&task);
+        self.broadcast_task("task_toggled", &task);
+        self.remember(snapshot);
+        self.autosave();
+        Ok(task)
+    }
+
+    /// Toggle the first task whose text matches exactly
+    #[http]
+    async fn toggle_task_by_text(&mut self, text: String) -> Result<TodoItem, TodoError> {
+        self.count_call();
+        let snapshot = self.snapshot();
+        let task = self
+            .tasks
+            .iter_mut()
+            .find(|t| t.text == text)
+            .ok_or_else(|| TodoError::NotFound(format!("Task with text '{}' not found", text)))?;
+        task.completed = !task.completed;
+        task.touch();
+        kiprintln!("Task toggled: {:?}", task);
+        let task = task.clone();
+        self.record_toggle(&task);
+        self.broadcast_task("task_toggled", &task);
+        self.remember(snapshot);
+        self.autosave();
+        Ok(task)
+    }
+
+    /// Toggle a task and return the whole updated list, saving a `get_tasks` round-trip
+    #[http]
+    async fn toggle_and_list(&mut self, task_id: String) -> Result<Vec<TodoItem>, TodoError> {
+        self.count_call();
+        let snapshot = self.snapshot();
+        let task = self.task_mut(&task_id)?;
+        task.completed = !task.completed;
+        task.touch();
+        kiprintln!("Task toggled: {:?}", task);
+        let task = task.clone();
+        self.record_toggle(&task);
+        self.broadcast_task("task_toggled", &task);
+        self.remember(snapshot);
+        self.autosave();
+
+        Ok(self.tasks.clone())
+    }
+
+    /// Toggle several tasks at once, skipping unknown ids
+    #[http]
+    async fn toggle_tasks(&mut self, task_ids: Vec<String>) -> Result<Vec<TodoItem>, TodoError> {
+        self.count_call();
+        let snapshot = self.snapshot();
+        let mut toggled = Vec::new();
+        for task_id in &task_ids {
+            if let Ok(task) = self.task_mut(task_id) {
+                task.completed = !task.completed;
+                task.touch();
+                toggled.push(task.clone());
+            }
+        }
+
+        if toggled.is_empty() {
+            return Err(TodoError::NotFound(
+                "None of the given task ids were found".to_string(),
+            ));
+        }
+        for task in &toggled {
+            self.record_toggle(task);
+            self.broadcast_task("task_toggled", task);
+        }
+        kiprintln!("Toggled {} of {} tasks", toggled.len(), task_ids.len());
+        self.remember(snapshot);
+        self.autosave();
+
+        Ok(toggled)
+    }
+
+    /// Change the text of an existing todo task.
+    /// Rejects the change if `expected_version` does not match the stored version
+    #[http]
+    async fn edit_task(
+        &mut self,
+        task_id: String,
+        text: String,
+        expected_version: u64,
+    ) -> Result<TodoItem, TodoError> {
+        self.count_call();
+        let snapshot = self.snapshot();
+        kiprintln!("Editing task: {}", task_id);
+
+        if text.trim().is_empty() {
+            return Err(TodoError::empty_text());
+        }
+
+        let task = self.task_mut(&task_id)?;
+        if task.version != expected_version {
+            return Err(TodoError::Conflict("version conflict".to_string()));
+        }
+        task.text = text;
+        task.touch();
+        kiprintln!("Task edited: {:?}", task);
+        let task = task.clone();
+        self.broadcast_task("task_edited", &task);
+        self.remember(snapshot);
+        self.autosave();
+        Ok(task)
+    }
+
+    /// Apply only the provided fields of `patch` to a task. A patch that changes
+    /// nothing returns the task untouched, without bumping its version.
+    #[http]
+    async fn patch_task(
+        &mut self,
+        task_id: String,
+        patch: TaskPatch,
+    ) -> Result<TodoItem, TodoError> {
+        self.count_call();
+        let snapshot = self.snapshot();
+        if let Some(text) = &patch.text {
+            if text.trim().is_empty() {
+                return Err(TodoError::empty_text());
+            }
+        }
+
+        let task = self.task_mut(&task_id)?;
+        let text = patch.text.filter(|text| *text != task.text);
+        let toggled = patch.completed.is_some_and(|c| c != task.completed);
+        if text.is_none() && !toggled {
+            // Nothing would change: leave the version alone and skip the broadcast
+            return Ok(task.clone());
+        }
+        if let Some(text) = text {
+            task.text = text;
+        }
+        if toggled {
+            task.completed = !task.completed;
+        }
+        task.touch();
+        kiprintln!("Task patched: {:?}", task);
+        let task = task.clone();
+        if toggled {
+            self.record_toggle(&task);
+        }
+        self.broadcast_task("task_edited", &task);
+        self.remember(snapshot);
+        self.autosave();
+        Ok(task)
+    }
+
+    /// Delete a todo task along with all of its subtasks
+    #[http]
+    async fn delete_task(&mut self, task_id: String) -> Result<DeleteResult, TodoError> {
+        self.count_call();
+        let snapshot = self.snapshot();
+        let deleted_ids = self
+            .remove_task(&task_id)
+            .ok_or_else(|| TodoError::task_not_found(&task_id))?;
+        kiprintln!("Deleted {} tasks: {:?}", deleted_ids.len(), deleted_ids);
+
+        self.broadcast(serde_json::json!({
+            "type": "task_deleted",
+            "id": task_id,
+            "deleted_ids": deleted_ids,
+            "tasks": self.tasks
+        }));
+        self.remember(snapshot);
+        self.autosave();
+
+        Ok(DeleteResult { deleted_ids })
+    }
+
+    /// Delete several tasks (and their subtasks) at once. Returns the ids actually
+    /// removed; unknown ids are skipped, so missing ones can be spotted by the caller.
+    #[http]
+    async fn delete_tasks(&mut self, task_ids: Vec<String>) -> Result<Vec<String>, TodoError> {
+        self.count_call();
+        let snapshot = self.snapshot();
+        let mut deleted_ids = Vec::new();
+        for task_id in &task_ids {
+            if let Some(ids) = self.remove_task(task_id) {
+                deleted_ids.extend(ids);
+            }
+        }
+        if deleted_ids.is_empty() {
+            return Ok(deleted_ids);
+        }
+        kiprintln!("Deleted {} tasks: {:?}", deleted_ids.len(), deleted_ids);
+
+        self.broadcast(serde_json::json!({
+            "type": "task_deleted",
+            "deleted_ids": deleted_ids,
+            "tasks": self.tasks
+        }));
+        self.remember(snapshot);
+        self.autosave();
+
+        Ok(deleted_ids)
+    }
+
+    /// Remove all completed tasks, returning how many were removed. Their
+    /// remaining subtasks are re-parented as in `archive_completed`.
+    #[http]
+    async fn clear_completed(&mut self) -> Result<usize, TodoError> {
+        self.count_call();
+        let snapshot = self.snapshot();
+        let (removed_tasks, kept): (Vec<TodoItem>, Vec<TodoItem>) =
+            self.tasks.drain(..).partition(|t| t.completed);
+        self.tasks = kept;
+        if removed_tasks.is_empty() {
+            return Ok(0);
+        }
+        self.reparent_orphans(&removed_tasks);
+        self.reindex();
+
+        let deleted_ids: Vec<String> = removed_tasks.iter().map(|t| t.id.clone()).collect();
+        for id in &deleted_ids {
+            self.record_event(TaskEvent::Deleted { id: id.clone() });
+        }
+        kiprintln!("Cleared {} completed tasks", deleted_ids.len());
+
+        self.broadcast(serde_json::json!({
+            "type": "task_deleted",
+            "deleted_ids": deleted_ids,
+            "tasks": self.tasks
+        }));
+        self.remember(snapshot);
+        self.autosave();
+
+        Ok(deleted_ids.len())
+    }
+
+    /// Remove completed tasks last updated before `cutoff_ms`, returning how many
+    /// were removed. Their remaining subtasks are re-parented as in `archive_completed`.
+    #[http]
+    async fn prune_completed_before(&mut self, cutoff_ms: u64) -> Result<usize, TodoError> {
+        self.count_call();
+        let snapshot = self.snapshot();
+        let (pruned, kept): (Vec<TodoItem>, Vec<TodoItem>) = self
+            .tasks
+            .drain(..)
+            .partition(|t| t.completed && t.updated_at_ms < cutoff_ms);
+        self.tasks = kept;
+        if pruned.is_empty() {
+            return Ok(0);
+        }
+        self.reparent_orphans(&pruned);
+        self.reindex();
+
+        let deleted_ids: Vec<String> = pruned.iter().map(|t| t.id.clone()).collect();
+        for id in &deleted_ids {
+            self.record_event(TaskEvent::Deleted { id: id.clone() });
+        }
+        kiprintln!(
+            "Pruned {} completed tasks before {}",
+            deleted_ids.len(),
+            cutoff_ms
+        );
+
+        self.broadcast(serde_json::json!({
+            "type": "task_deleted",
+            "deleted_ids": deleted_ids,
+            "tasks": self.tasks
+        }));
+        self.remember(snapshot);
+        self.autosave();
+
+        Ok(deleted_ids.len())
+    }
+
+    /// Move all completed tasks into the archive, returning how many moved.
+    /// Pending subtasks of an archived task are re-parented onto its nearest
+    /// ancestor still in the list, or become top-level tasks.
+    #[http]
+    async fn archive_completed(&mut self) -> Result<usize, TodoError> {
+        self.count_call();
+        let snapshot = self.snapshot();
+        let (completed, pending): (Vec<TodoItem>, Vec<TodoItem>) =
+            self.tasks.drain(..).partition(|t| t.completed);
+        if completed.is_empty() {
+            self.tasks = pending;
+            return Ok(0);
+        }
+
+        self.tasks = pending;
+        self.reparent_orphans(&completed);
+        self.reindex();
+
+        let archived_ids: Vec<String> = completed.iter().map(|t| t.id.clone()).collect();
+        for id in &archived_ids {
+            self.record_event(TaskEvent::Deleted { id: id.clone() });
+        }
+        self.archived.extend(completed);
+        kiprintln!("Archived {} completed tasks", archived_ids.len());
+
+        self.broadcast(serde_json::json!({
+            "type": "task_deleted",
+            "deleted_ids": archived_ids,
+            "tasks": self.tasks
+        }));
+        self.remember(snapshot);
+        self.autosave();
+
+        Ok(archived_ids.len())
+    }
+
+    /// Get archived tasks, oldest archive first
+    #[http]
+    fn get_archived(&self) -> Vec<TodoItem> {
+        self.count_call();
+        self.archived.clone()
+    }
+
+    /// Move a task from the archive back to the end of the list as pending.
+    /// Refused if a task with the same id is already in the list.
+    #[http]
+    async fn unarchive_task(&mut self, task_id: String) -> Result<TodoItem, TodoError> {
+        self.count_call();
+        let snapshot = self.snapshot();
+        let i = self
+            .archived
+            .iter()
+            .position(|t| t.id == task_id)
+            .ok_or_else(|| {
+                TodoError::NotFound(format!("Archived task with id '{}' not found", task_id))
+            })?;
+        if self.position(&task_id).is_some() {
+            return Err(TodoError::Conflict(format!(
+                "A task with id '{}' is already in the list",
+                task_id
+            )));
+        }
+        self.ensure_capacity()?;
+
+        let mut task = self.archived.remove(i);
+        task.completed = false;
+        task.touch();
+        self.push_task(task.clone());
+        kiprintln!("Unarchived task: {:?}", task);
+        self.broadcast_task("task_added", &task);
+        self.remember(snapshot);
+        self.autosave();
+
+        Ok(task)
+    }
+
+    /// Set the completion status of every task, returning how many changed
+    #[http]
+    async fn set_all_completed(&mut self, completed: bool) -> Result<usize, TodoError> {
+        self.count_call();
+        let snapshot = self.snapshot();
+        let mut changed = Vec::new();
+        for task in self.tasks.iter_mut().filter(|t| t.completed != completed) {
+            task.completed = completed;
+            task.touch();
+            changed.push(task.clone());
+        }
+        if changed.is_empty() {
+            return Ok(0);
+        }
+        for task in &changed {
+            self.record_toggle(task);
+        }
+        let changed = changed.len();
+        kiprintln!("Set {} tasks to completed={}", changed, completed);
+        self.broadcast_overview();
+        self.remember(snapshot);
+        self.autosave();
+        Ok(changed)
+    }
+
+    /// Replace the whole task list, rejecting empty texts and duplicate ids
+    #[http]
+    async fn replace_all_tasks(&mut self, tasks: Vec<TodoItem>) -> Result<usize, TodoError> {
+        self.count_call();
+        let snapshot = self.snapshot();
+        validate_task_list(&tasks)?;
+        ensure_fits(tasks.len())?;
+
+        self.tasks = tasks;
+        self.reindex();
+        self.record_changes(&snapshot.tasks);
+        kiprintln!("Replaced task list with {} tasks", self.tasks.len());
+        self.broadcast_overview();
+        self.remember(snapshot);
+        self.autosave();
+
+        Ok(self.tasks.len())
+    }
+
+    /// Serialize the task list to a JSON string snapshot
+    #[http]
+    async fn export_tasks(&self) -> Result<String, TodoError> {
+        self.count_call();
+        serde_json::to_string(&self.tasks)
+            .map_err(|e| TodoError::Internal(format!("Failed to export tasks: {}", e)))
+    }
+
+    /// Render the task list as JSON, CSV or plain text
+    #[http]
+    async fn get_tasks_as(&self, format: ExportFormat) -> Result<String, TodoError> {
+        self.count_call();
+        match format {
+            ExportFormat::Json => serde_json::to_string(&self.tasks)
+                .map_err(|e| TodoError::Internal(format!("Failed to export tasks: {}", e))),
+            ExportFormat::Csv => {
+                let mut csv = String::from("id,text,completed\n");
+                for task in &self.tasks {
+                    csv.push_str(&format!(
+                        "{},{},{}\n",
+                        csv_field(&task.id),
+                        csv_field(&task.text),
+                        task.completed
+                    ));
+                }
+                Ok(csv)
+            }
+            ExportFormat::PlainText => Ok(self
+                .tasks
+                .iter()
+                .map(|t| format!("[{}] {}\n", if t.completed { "x" } else { " " }, t.text))
+                .collect()),
+        }
+    }
+
+    /// Length in bytes of the persisted state serialized as JSON
+    #[http]
+    fn state_size_bytes(&self) -> Result<usize, TodoError> {
+        self.count_call();
+        serde_json::to_vec(self)
+            .map(|bytes| bytes.len())
+            .map_err(|e| TodoError::Internal(format!("Failed to serialize state: {}", e)))
+    }
+
+    /// Integrity check: tasks with blank text, or an id that is neither a UUID
+    /// nor one handed out by `renumber_tasks`. Always empty unless a mutation
+    /// path let bad data through.
+    #[http]
+    fn find_invalid_tasks(&self) -> Vec<TodoItem> {
+        self.count_call();
+        let renumbered: HashSet<&str> = self.renumbered_ids.iter().map(String::as_str).collect();
+        self.tasks
+            .iter()
+            .filter(|t| {
+                let valid_id = Uuid::parse_str(&t.id).is_ok() || renumbered.contains(t.id.as_str());
+                t.text.trim().is_empty() || !valid_id
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Load tasks from a JSON snapshot produced by `export_tasks`, rejecting it
+    /// like `replace_all_tasks` does if it has empty texts or duplicate ids. With
+    /// `merge` only tasks whose ids are not already present are appended;
+    /// otherwise the list is replaced. Returns the final task count.
+    #[http]
+    async fn import_tasks(&mut self, json: String, merge: bool) -> Result<usize, TodoError> {
+        self.count_call();
+        let snapshot = self.snapshot();
+        let tasks: Vec<TodoItem> = serde_json::from_str(&json)
+            .map_err(|e| TodoError::Validation(format!("Invalid task JSON: {}", e)))?;
+        validate_task_list(&tasks)?;
+
+        if merge {
+            let new_tasks: Vec<TodoItem> = tasks
+                .into_iter()
+                .filter(|t| self.position(&t.id).is_none())
+                .collect();
+            ensure_fits(self.tasks.len() + new_tasks.len())?;
+            for task in new_tasks {
+                self.push_task(task);
+            }
+        } else {
+            ensure_fits(tasks.len())?;
+            self.tasks = tasks;
+            self.reindex();
+            self.record_changes(&snapshot.tasks);
+        }
+        kiprintln!("Imported tasks (merge={}), now {}", merge, self.tasks.len());
+        self.broadcast_overview();
+        self.remember(snapshot);
+        self.autosave();
+
+        Ok(self.tasks.len())
+    }
+
+    /// Add tasks from `text,completed` or `id,text,completed` CSV rows, as written
+    /// by `get_tasks_as(Csv)`. A leading header row is skipped and any id column
+    /// is ignored: every task gets a fresh id. With `replace` the existing list is
+    /// cleared first. Returns how many tasks were imported.
+    #[http]
+    async fn import_csv(&mut self, csv: String, replace: bool) -> Result<usize, TodoError> {
+        self.count_call();
+        let snapshot = self.snapshot();
+        let mut new_tasks = Vec::new();
+        let malformed =
+            |line: usize| TodoError::Validation(format!("Malformed CSV on line {}", line));
+        let records = parse_csv(&csv).map_err(malformed)?;
+        for (i, (line, fields)) in records.iter().enumerate() {
+            let row: Vec<&str> = fields.iter().map(|f| f.trim()).collect();
+            let is_header =
+                i == 0 && (row == ["text", "completed"] || row == ["id", "text", "completed"]);
+            if is_header || fields.iter().all(|f| f.trim().is_empty()) {
+                continue;
+            }
+            let ([text, completed] | [_, text, completed]) = fields.as_slice() else {
+                return Err(malformed(*line));
+            };
+            if text.trim().is_empty() {
+                return Err(malformed(*line));
+            }
+            let mut task = TodoItem::new(text.clone());
+            task.completed = completed.trim().parse().map_err(|_| malformed(*line))?;
+            new_tasks.push(task);
+        }
+
+        let existing = if replace { 0 } else { self.tasks.len() };
+        ensure_fits(existing + new_tasks.len())?;
+        if replace {
+            for task in std::mem::take(&mut self.tasks) {
+                self.record_event(TaskEvent::Deleted { id: task.id });
+            }
+            self.reindex();
+        }
+        let imported = new_tasks.len();
+        for task in new_tasks {
+            self.push_task(task);
+        }
+        kiprintln!("Imported {} tasks from CSV (replace={})", imported, replace);
+        self.broadcast_overview();
+        self.remember(snapshot);
+        self.autosave();
+
+        Ok(imported)
+    }
+
+    /// Clear all tasks; WebSocket channels are left untouched
+    #[http]
+    async fn reset_state(&mut self) -> Result<(), TodoError> {
+        self.count_call();
+        let snapshot = self.snapshot();
+        kiprintln!("Resetting state ({} tasks)", self.tasks.len());
+        for task in std::mem::take(&mut self.tasks) {
+            self.record_event(TaskEvent::Deleted { id: task.id });
+        }
+        self.reindex();
+        self.broadcast_overview();
+        self.remember(snapshot);
+        self.autosave();
+        Ok(())
+    }
+
+    /// Set the priority of a todo task
+    #[http]
+    async fn set_priority(
+        &mut self,
+        task_id: String,
+        priority: Priority,
+    ) -> Result<TodoItem, TodoError> {
+        self.count_call();
+        let snapshot = self.snapshot();
+        kiprintln!("Setting priority of task {} to {:?}", task_id, priority);
+
+        let task = self.task_mut(&task_id)?;
+        task.priority = priority;
+        task.touch();
+        let task = task.clone();
+        self.broadcast_task("task_edited", &task);
+        self.remember(snapshot);
+        self.autosave();
+        Ok(task)
+    }
+
+    /// Set or clear (with `None`) the due date of a todo task
+    #[http]
+    async fn set_due_date(
+        &mut self,
+        task_id: String,
+        due_at: Option<u64>,
+    ) -> Result<TodoItem, TodoError> {
+        self.count_call();
+        let snapshot = self.snapshot();
+        kiprintln!("Setting due date of task {} to {:?}", task_id, due_at);
+
+        let task = self.task_mut(&task_id)?;
+        task.due_at = due_at;
+        task.touch();
+        let task = task.clone();
+        self.broadcast_task("task_edited", &task);
+        self.remember(snapshot);
+        self.autosave();
+        Ok(task)
+    }
+
+    /// Add a tag to a todo task (no-op if already present)
+    #[http]
+    async fn add_tag(&mut self, task_id: String, tag: String) -> Result<TodoItem, TodoError> {
+        self.count_call();
+        let snapshot = self.snapshot();
+        let task = self.task_mut(&task_id)?;
+        if task.tags.contains(&tag) {
+            return Ok(task.clone());
+        }
+        task.tags.push(tag);
+        task.touch();
+        let task = task.clone();
+        self.broadcast_task("task_edited", &task);
+        self.remember(snapshot);
+        self.autosave();
+        Ok(task)
+    }
+
+    /// Remove a tag from a todo task (no-op if absent)
+    #[http]
+    async fn remove_tag(&mut self, task_id: String, tag: String) -> Result<TodoItem, TodoError> {
+        self.count_call();
+        let snapshot = self.snapshot();
+        let task = self.task_mut(&task_id)?;
+        let before = task.tags.len();
+        task.tags.retain(|t| *t != tag);
+        if task.tags.len() == before {
+            return Ok(task.clone());
+        }
+        task.touch();
+        let task = task.clone();
+        self.broadcast_task("task_edited", &task);
+        self.remember(snapshot);
+        self.autosave();
+        Ok(task)
+    }
 
-#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
-pub struct Item {
-    pub id: String,
-}
+    /// Move completed tasks to the end, keeping relative order within each group
+    #[http]
+    async fn sort_completed_last(&mut self) -> Result<Vec<TodoItem>, TodoError> {
+        self.count_call();
+        let snapshot = self.snapshot();
+        let (mut pending, completed): (Vec<TodoItem>, Vec<TodoItem>) =
+            self.tasks.drain(..).partition(|t| !t.completed);
+        pending.extend(completed);
+        self.tasks = pending;
+        if self.tasks == snapshot.tasks {
+            return Ok(self.tasks.clone());
+        }
+        self.reindex();
+        self.remember(snapshot);
+        self.autosave();
+        Ok(self.tasks.clone())
+    }
 
-// =============================================================================
-// TESTING/DEMO DATA STRUCTURES
-// =============================================================================
+    /// Remove tasks whose trimmed, case-insensitive text repeats an earlier task.
+    /// The first occurrence is kept and marked completed if any duplicate was;
+    /// subtasks of removed duplicates are re-parented onto it.
+    #[http]
+    async fn dedupe_tasks(&mut self) -> Result<usize, TodoError> {
+        self.count_call();
+        let snapshot = self.snapshot();
+        let mut first_by_text: HashMap<String, usize> = HashMap::new();
+        let mut replaced_by: HashMap<String, String> = HashMap::new();
+        let mut kept: Vec<TodoItem> = Vec::with_capacity(self.tasks.len());
 
-/// Generic request structure for API testing and demos
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ApiRequest {
-    pub message: String,
-    pub id: Option<u32>,
-}
+        for task in self.tasks.drain(..) {
+            let key = task.text.trim().to_lowercase();
+            match first_by_text.get(&key) {
+                Some(&i) => {
+                    if task.completed && !kept[i].completed {
+                        kept[i].completed = true;
+                        kept[i].touch();
+                    }
+                    replaced_by.insert(task.id, kept[i].id.clone());
+                }
+                None => {
+                    first_by_text.insert(key, kept.len());
+                    kept.push(task);
+                }
+            }
+        }
 
-/// Generic response structure for API testing and demos
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ApiResponse {
-    pub status: String,
-    pub data: String,
-    pub path: Option<String>,
-    pub method: Option<String>,
-}
+        for task in &mut kept {
+            if let Some(new_parent) = task.parent_id.as_ref().and_then(|p| replaced_by.get(p)) {
+                // A task can't become its own parent if it absorbed that parent's text
+                task.parent_id = (*new_parent != task.id).then(|| new_parent.clone());
+                task.touch();
+            }
+        }
 
-impl ApiResponse {
-    fn new(data: &str) -> Self {
-        Self {
-            status: "success".to_string(),
-            data: data.to_string(),
-            path: get_path(),
-            method: get_http_method(),
+        self.tasks = kept;
+        if replaced_by.is_empty() {
+            return Ok(0);
         }
+        self.reindex();
+        self.record_changes(&snapshot.tasks);
+        kiprintln!("Removed {} duplicate tasks", replaced_by.len());
+        self.broadcast_overview();
+        self.remember(snapshot);
+        self.autosave();
+
+        Ok(replaced_by.len())
     }
-}
 
-// =============================================================================
-// APPLICATION STATE
-// =============================================================================
+    /// Restore the tasks and archive captured before the last mutation. Undo
+    /// itself is not undoable: the snapshot is consumed, so a second undo is an error.
+    #[http]
+    async fn undo(&mut self) -> Result<(), TodoError> {
+        self.count_call();
+        let snapshot = self
+            .last_snapshot
+            .take()
+            .ok_or_else(|| TodoError::Conflict("Nothing to undo".to_string()))?;
+        let before = std::mem::replace(&mut self.tasks, snapshot.tasks);
+        self.archived = snapshot.archived;
+        self.reindex();
+        self.record_changes(&before);
+        kiprintln!("Undid last mutation");
+        self.autosave();
+        self.broadcast_overview();
+        Ok(())
+    }
 
-/// Main application state containing todo tasks and WebSocket connections
-#[derive(PartialEq, Clone, Default, Debug, Serialize, Deserialize)]
-pub struct TodoState {
-    /// List of todo tasks
-    tasks: Vec<TodoItem>,
-    /// Active WebSocket channel IDs (not serialized)
-    #[serde(skip)]
-    ws_channels: HashSet<u32>,
-}
+    /// Logged mutations with a timestamp strictly after `since_ms`, oldest first
+    #[http]
+    fn get_events(&self, since_ms: u64) -> Vec<(u64, TaskEvent)> {
+        self.count_call();
+        self.events
+            .iter()
+            .filter(|(ts, _)| *ts > since_ms)
+            .cloned()
+            .collect()
+    }
 
-// =============================================================================
-// HYPERPROCESS CONFIGURATION
-// =============================================================================
+    /// Logged events for a single task, oldest first
+    #[http]
+    fn task_history(&self, task_id: String) -> Vec<TaskEvent> {
+        self.count_call();
+        self.events
+            .iter()
+            .filter(|(_, event)| event.task_id() == task_id)
+            .map(|(_, event)| event.clone())
+            .collect()
+    }
 
-#[hyperprocess(
-    name = "todo",
-    ui = Some(HttpBindingConfig::default()),
-    endpoints = vec![
-        Binding::Http {
-            path: "/health",
-            config: HttpBindingConfig::new(false, false, false, None),
-        },
-        Binding::Ws {
-            path: "/ws",
-            config: WsBindingConfig::new(false, false, false),
-        },
-        // Demo/testing endpoints
-        Binding::Http {
-            path: "/users",
-            config: HttpBindingConfig::new(false, false, false, None),
-        },
-        Binding::Http {
-            path: "/posts",
-            config: HttpBindingConfig::new(false, false, false, None),
-        },
-        Binding::Http {
-            path: "/api",
-            config: HttpBindingConfig::new(false, false, false, None),
-        },
-        Binding::Http {
-            path: "/admin",
-            config: HttpBindingConfig::new(false, false, false, None),
-        },
-        Binding::Http {
-            path: "/test",
-            config: HttpBindingConfig::new(false, false, false, None),
-        },
-        Binding::Http {
-            path: "/api/unknown",
-            config: HttpBindingConfig::new(false, false, false, None),
-        },
-        Binding::Http {
-            path: "/admin/dashboard", 
-            config: HttpBindingConfig::new(false, false, false, None),
-        },
-        Binding::Http {
-            path: "/test/something",
-            config: HttpBindingConfig::new(false, false, false, None),
-        },
-        Binding::Http {
-            path: "/api/upload",
-            config: HttpBindingConfig::new(false, false, false, None),
-        },
-        Binding::Http {
-            path: "/other/endpoint",
-            config: HttpBindingConfig::new(false, false, false, None),
-        },
-        Binding::Http {
-            path: "/anything",
-            config: HttpBindingConfig::new(false, false, false, None),
-        },
-        Binding::Http {
-            path: "/whatever",
-            config: HttpBindingConfig::new(false, false, false, None),
-        },
-        Binding::Http {
-            path: "/some/path",
-            config: HttpBindingConfig::new(false, false, false, None),
-        },
-        Binding::Http {
-            path: "/api/data",
-            config: HttpBindingConfig::new(false, false, false, None),
-        },
-    ],
-    save_config = SaveOptions::EveryMessage,
-    wit_world = "todo-template-dot-os-v0"
-)]
+    /// Reassign ids as "1", "2", ... in list order, remapping `parent_id` to
+    /// match. Numbers already used as ids in the archive are skipped, and a
+    /// `parent_id` naming no existing task is cleared, so nothing can collide
+    /// with a new id. The event log records each change as a deletion of the
+    /// old id and an add of the new one. Returns the (old, new) id pairs.
+    #[http]
+    async fn renumber_tasks(&mut self) -> Result<Vec<(String, String)>, TodoError> {
+        self.count_call();
+        let snapshot = self.snapshot();
+        let reserved: HashSet<&str> = self.archived.iter().map(|t| t.id.as_str()).collect();
+        let mut next = 0u64;
+        let mut mapping: HashMap<String, String> = HashMap::new();
+        for task in &self.tasks {
+            let new_id = loop {
+                next += 1;
+                let candidate = next.to_string();
+                if !reserved.contains(candidate.as_str()) {
+                    break candidate;
+                }
+            };
+            mapping.insert(task.id.clone(), new_id);
+        }
 
-// =============================================================================
-// CORE TODO APPLICATION IMPLEMENTATION
-// =============================================================================
+        let mut pairs = Vec::with_capacity(self.tasks.len());
+        for task in &mut self.tasks {
+            let new_id = mapping[&task.id].clone();
+            pairs.push((std::mem::replace(&mut task.id, new_id), task.id.clone()));
+            task.parent_id = task
+                .parent_id
+                .as_ref()
+                .and_then(|parent| mapping.get(parent).cloned());
+            task.touch();
+        }
+        self.reindex();
+        self.record_changes(&snapshot.tasks);
+        let known: HashSet<String> = self.renumbered_ids.iter().cloned().collect();
+        self.renumbered_ids.extend(
+            pairs
+                .iter()
+                .map(|(_, new_id)| new_id.clone())
+                .filter(|id| !known.contains(id)),
+        );
+        kiprintln!("Renumbered {} tasks", pairs.len());
+        self.broadcast_overview();
+        self.remember(snapshot);
+        self.autosave();
 
-impl TodoState {
-    /// Initialize the application state
-    #[init]
-    async fn initialize(&mut self) {
-        kiprintln!("Initializing todo list state");
-        self.tasks = Vec::new();
-        self.ws_channels = HashSet::new();
+        Ok(pairs)
     }
 
-    // -------------------------------------------------------------------------
-    // CORE TODO FUNCTIONALITY
-    // -------------------------------------------------------------------------
+    /// Zero-based position of a task in the list
+    #[http]
+    async fn task_index(&self, task_id: String) -> Result<usize, TodoError> {
+        self.count_call();
+        self.position(&task_id)
+            .ok_or_else(|| TodoError::task_not_found(&task_id))
+    }
 
-    /// Add a new todo task
+    /// Move a task to a new position (clamped to the list bounds)
     #[http]
-    async fn add_task(&mut self, text: String) -> Result<TodoItem, String> {
-        if text.trim().is_empty() {
-            return Err("Task text cannot be empty".to_string());
+    async fn reorder_task(
+        &mut self,
+        task_id: String,
+        new_index: usize,
+    ) -> Result<Vec<TodoItem>, TodoError> {
+        self.count_call();
+        let snapshot = self.snapshot();
+        let old_index = self
+            .position(&task_id)
+            .ok_or_else(|| TodoError::task_not_found(&task_id))?;
+
+        let new_index = new_index.min(self.tasks.len() - 1);
+        if new_index == old_index {
+            return Ok(self.tasks.clone());
         }
-        
-        let new_task = TodoItem {
-            id: Uuid::new_v4().to_string(),
-            text,
-            completed: false,
-        };
-        
-        self.tasks.push(new_task.clone());
-        kiprintln!("Added task: {:?}", new_task);
+        let task = self.tasks.remove(old_index);
+        self.tasks.insert(new_index, task);
+        self.reindex();
+        kiprintln!("Moved task {} from {} to {}", task_id, old_index, new_index);
+        self.broadcast_overview();
+        self.remember(snapshot);
+        self.autosave();
 
-        Ok(new_task)
+        Ok(self.tasks.clone())
     }
 
-    /// Get all todo tasks
+    /// Turn the handlers' explicit per-mutation save on or off, returning the new
+    /// setting. The framework's `EveryMessage` save is unaffected, so turning it
+    /// off never leaves state unsaved.
     #[http]
-    async fn get_tasks(&self, request: String) -> Result<Vec<TodoItem>, String> {
-        kiprintln!("Request: {:?}", request);
-        kiprintln!("Fetching tasks");
+    fn set_autosave(&mut self, enabled: bool) -> bool {
+        self.count_call();
+        kiprintln!("Autosave {}", if enabled { "enabled" } else { "disabled" });
+        self.autosave_enabled = enabled;
+        enabled
+    }
+
+    /// Persist the state immediately, regardless of the autosave setting
+    #[http]
+    fn force_save(&mut self) -> Result<(), TodoError> {
+        self.count_call();
+        self.save()
+    }
+
+    /// Swap the list positions of two tasks; swapping a task with itself is a no-op
+    #[http]
+    async fn swap_tasks(&mut self, id_a: String, id_b: String) -> Result<Vec<TodoItem>, TodoError> {
+        self.count_call();
+        let snapshot = self.snapshot();
+        let a = self
+            .position(&id_a)
+            .ok_or_else(|| TodoError::task_not_found(&id_a))?;
+        let b = self
+            .position(&id_b)
+            .ok_or_else(|| TodoError::task_not_found(&id_b))?;
+        if a != b {
+            self.tasks.swap(a, b);
+            self.reindex();
+            kiprintln!("Swapped tasks {} and {}", id_a, id_b);
+            self.broadcast_overview();
+            self.remember(snapshot);
+            self.autosave();
+        }
+
         Ok(self.tasks.clone())
     }
 
-    /// Toggle a todo task's completion status
+    /// HTTP handler calls counted so far, this one included
     #[http]
-    async fn toggle_task(&mut self, task_id: String) -> Result<TodoItem, String> {
-        kiprintln!("Toggling task: {}", task_id);
-        
-        if let Some(task) = self.tasks.iter_mut().find(|t| t.id == task_id) {
-            task.completed = !task.completed;
-            kiprintln!("Task toggled: {:?}", task);
-            Ok(task.clone())
-        } else {
-            Err(format!("Task with id '{}' not found", task_id))
+    fn get_call_count(&self) -> u64 {
+        self.count_call();
+        self.call_count.get()
+    }
+
+    /// Zero the HTTP call counter, returning the count before the reset
+    /// (which includes this call)
+    #[http]
+    fn reset_call_count(&mut self) -> u64 {
+        self.count_call();
+        self.call_count.replace(0)
+    }
+
+    /// Number of currently connected WebSocket clients
+    #[http]
+    fn connected_clients(&self) -> usize {
+        self.count_call();
+        self.ws_channels.len()
+    }
+
+    /// Connected WebSocket channels with their `set_name` names, by channel id
+    #[http]
+    fn list_clients(&self) -> Vec<(u32, Option<String>)> {
+        self.count_call();
+        let mut clients: Vec<(u32, Option<String>)> = self
+            .ws_channels
+            .iter()
+            .map(|&id| (id, self.channel_names.get(&id).cloned()))
+            .collect();
+        clients.sort_by_key(|(id, _)| *id);
+        clients
+    }
+
+    /// Forget a WebSocket channel as if it had closed, returning whether it was tracked
+    #[http]
+    fn disconnect_channel(&mut self, channel_id: u32) -> Result<bool, TodoError> {
+        self.count_call();
+        self.subscribers.remove(&channel_id);
+        self.channel_names.remove(&channel_id);
+        let was_present = self.ws_channels.remove(&channel_id);
+        kiprintln!(
+            "Disconnected channel {} (tracked: {})",
+            channel_id,
+            was_present
+        );
+        Ok(was_present)
+    }
+
+    /// Push a heartbeat frame to every connected channel, subscribed or not,
+    /// returning how many were notified
+    #[http]
+    fn trigger_heartbeat(&self) -> usize {
+        self.count_call();
+        let heartbeat = serde_json::json!({
+            "type": "heartbeat",
+            "ts": now_ms()
+        });
+        for &channel_id in &self.ws_channels {
+            send_ws_json(channel_id, &heartbeat);
+        }
+        self.ws_channels.len()
+    }
+
+    /// Liveness probe with basic state information
+    #[http(method = "GET", path = "/health")]
+    fn health(&self) -> HealthStatus {
+        self.count_call();
+        HealthStatus {
+            status: "ok".to_string(),
+            task_count: self.tasks.len(),
+            ws_clients: self.ws_channels.len(),
+            uptime_ms: self.uptime_ms(),
+        }
+    }
+
+    /// Zero-cost liveness check that never touches the task list
+    #[http(method = "GET", path = "/ping")]
+    fn ping(&self) -> String {
+        self.count_call();
+        "pong".to_string()
+    }
+
+    /// Report which build is running
+    #[http(method = "GET", path = "/version")]
+    fn version(&self) -> VersionInfo {
+        self.count_call();
+        VersionInfo {
+            name: env!("CARGO_PKG_NAME").to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            wit_world: "todo-template-dot-os-v0".to_string(),
         }
     }
 
+    /// Current server clock in unix milliseconds, for skew and round-trip checks
+    #[http(method = "GET", path = "/time")]
+    fn server_time(&self) -> u64 {
+        self.count_call();
+        now_ms()
+    }
+
+    /// Paths this process binds, for comparing against the expected routes
+    #[http]
+    fn list_endpoints(&self) -> Vec<String> {
+        self.count_call();
+        ENDPOINT_PATHS.iter().map(|p| p.to_string()).collect()
+    }
+
     #[ws]
     fn websocket(&mut self, channel_id: u32, message_type: WsMessageType, blob: LazyLoadBlob) {
+        // Track the channel on its first message so broadcasts reach it
+        if !matches!(message_type, WsMessageType::Close) {
+            self.ws_channels.insert(channel_id);
+        }
+
         match message_type {
             WsMessageType::Text => {
                 // Get the message from the blob
-                if let Ok(message) = String::from_utf8(blob.bytes.clone()) {
-                    kiprintln!("Received WebSocket text message: {}", message);
-                    // Parse the message as JSON
-                    if let Ok(json) = serde_json::from_str::<serde_json::Value>(&message) {
-                        // Handle different message types
-                        if let Some(action) = json.get("action").and_then(|v| v.as_str()) {
-                            match action {
-                                "get_tasks" => {
-                                    // Send current tasks to the requesting client
-                                    let response = serde_json::json!({
-                                        "type": "tasks_overview",
-                                        "tasks": self.tasks
-                                    });
-
-                                    let response_bytes = response.to_string().into_bytes();
-
-                                    let response_blob = LazyLoadBlob {
-                                        mime: Some("application/json".to_string()),
-                                        bytes: response_bytes,
-                                    };
-                                    send_ws_push(channel_id, WsMessageType::Text, response_blob);
-                                }
-                                "add_task" => {
-                                    if let Some(text) = json.get("text").and_then(|v| v.as_str()) {
-                                        if !text.trim().is_empty() {
-                                            let new_task = TodoItem {
-                                                id: Uuid::new_v4().to_string(),
-                                                text: text.to_string(),
-                                                completed: false,
-                                            };
-                                            self.tasks.push(new_task.clone());
-
-                                            // Broadcast the update to all connected clients
-                                            let broadcast = serde_json::json!({
-                                                "type": "task_added",
-                                                "task": new_task,
-                                                "tasks": self.tasks
-                                            });
-                                            let response_bytes = broadcast.to_string().into_bytes();
-
-                                            let response_blob = LazyLoadBlob {
-                                                mime: Some("application/json".to_string()),
-                                                bytes: response_bytes,
-                                            };
-                                            send_ws_push(
-                                                channel_id,
-                                                WsMessageType::Text,
-                                                response_blob,
-                                            );
-                                        }
-                                    }
-                                }
-                                "toggle_task" => {
-                                    if let Some(id) = json.get("id").and_then(|v| v.as_str()) {
-                                        if let Some(task) =
-                                            self.tasks.iter_mut().find(|t| t.id == id)
-                                        {
-                                            task.completed = !task.completed;
-
-                                            // Broadcast the update to all connected clients
-                                            let broadcast = serde_json::json!({
-                                                "type": "task_toggled",
-                                                "task": task.clone(),
-                                                "tasks": self.tasks
-                                            });
-                                            let response_bytes = broadcast.to_string().into_bytes();
-
-                                            let response_blob = LazyLoadBlob {
-                                                mime: Some("application/json".to_string()),
-                                                bytes: response_bytes,
-                                            };
-                                            send_ws_push(
-                                                channel_id,
-                                                WsMessageType::Text,
-                                                response_blob,
-                                            );
-                                        }
-                                    }
-                                }
-                                _ => {
-                                    println!("Unknown WebSocket action: {}", action);
-                                }
-                            }
-                        }
-                    }
+                let message = String::from_utf8_lossy(&blob.bytes);
+                kiprintln!("Received WebSocket text message: {}", message);
+
+                // Parse the message as JSON and dispatch on its action
+                let result = serde_json::from_str::<serde_json::Value>(&message)
+                    .map_err(|e| TodoError::Validation(format!("invalid JSON: {}", e)))
+                    .and_then(|json| self.handle_ws_action(channel_id, &json));
+                if let Err(e) = result {
+                    println!("WebSocket error on channel {}: {}", channel_id, e);
+                    send_ws_error(channel_id, &e.to_string());
                 }
             }
             WsMessageType::Binary => {
                 println!("Received WebSocket binary message");
+                // Echo the payload back unchanged so binary round-trips can be verified
+                let echo_blob = LazyLoadBlob {
+                    mime: blob.mime.clone(),
+                    bytes: blob.bytes,
+                };
+                send_ws_push(channel_id, WsMessageType::Binary, echo_blob);
             }
             WsMessageType::Ping => {
                 println!("Received WebSocket ping message");
+                // Answer with a Pong carrying the ping payload for keepalive
+                let pong_blob = LazyLoadBlob {
+                    mime: None,
+                    bytes: blob.bytes,
+                };
+                send_ws_push(channel_id, WsMessageType::Pong, pong_blob);
             }
             WsMessageType::Pong => {
                 println!("Received WebSocket pong message");
             }
             WsMessageType::Close => {
                 println!("Received WebSocket close message");
+                self.ws_channels.remove(&channel_id);
+                self.subscribers.remove(&channel_id);
+                self.channel_names.remove(&channel_id);
             }
         }
     }
@@ -332,6 +2780,7 @@ impl TodoState {
     /// Demo handler: GET /users
     #[http(method = "GET", path = "/users")]
     fn get_users(&mut self) -> ApiResponse {
+        self.count_call();
         kiprintln!("GET /users");
         ApiResponse::new("List of users")
     }
@@ -339,6 +2788,7 @@ impl TodoState {
     /// Demo handler: POST /users (with parameters)
     #[http(method = "POST", path = "/users")]
     async fn create_user(&mut self, req: ApiRequest) -> Result<ApiResponse, String> {
+        self.count_call();
         kiprintln!("POST /users: {:?}", req);
         Ok(ApiResponse::new(&format!("Created user: {}", req.message)))
     }
@@ -346,6 +2796,7 @@ impl TodoState {
     /// Demo handler: GET /posts
     #[http(method = "GET", path = "/posts")]
     fn get_posts(&mut self) -> ApiResponse {
+        self.count_call();
         kiprintln!("GET /posts");
         ApiResponse::new("List of posts")
     }
@@ -353,21 +2804,176 @@ impl TodoState {
     /// Demo handler: POST /api/data (with parameters)
     #[http(method = "POST", path = "/api/data")]
     async fn process_data(&mut self, req: ApiRequest) -> Result<ApiResponse, String> {
+        self.count_call();
         kiprintln!("POST /api/data: {:?}", req);
         Ok(ApiResponse::new(&format!("Processed: {}", req.message)))
     }
 
+    /// Demo handler: POST /echo-delay (sleeps for the requested time, then echoes)
+    #[http(method = "POST", path = "/echo-delay")]
+    async fn echo_delay(&mut self, req: DelayRequest) -> Result<ApiResponse, String> {
+        self.count_call();
+        let delay_ms = req.delay_ms.min(MAX_DELAY_MS);
+        kiprintln!("POST /echo-delay: sleeping {}ms", delay_ms);
+        sleep(delay_ms)
+            .await
+            .map_err(|e| format!("Sleep failed: {:?}", e))?;
+        Ok(ApiResponse::new(&format!(
+            "Echo after {}ms: {}",
+            delay_ms, req.message
+        )))
+    }
+
+    /// Demo handler: POST /slow-steps (sleeps `step_ms` up to `steps` times,
+    /// stopping early rather than exceeding `MAX_DELAY_MS` in total)
+    #[http(method = "POST", path = "/slow-steps")]
+    async fn slow_steps(&mut self, req: StepRequest) -> Result<ApiResponse, String> {
+        self.count_call();
+        let started = now_ms();
+        let mut slept_ms = 0;
+        for step in 1..=req.steps {
+            let delay_ms = req.step_ms.min(MAX_DELAY_MS - slept_ms);
+            if delay_ms == 0 {
+                break;
+            }
+            sleep(delay_ms)
+                .await
+                .map_err(|e| format!("Sleep failed: {:?}", e))?;
+            slept_ms += delay_ms;
+            kiprintln!("POST /slow-steps: step {}/{} done", step, req.steps);
+        }
+        Ok(ApiResponse::new(&format!(
+            "Completed in {}ms",
+            now_ms().saturating_sub(started)
+        )))
+    }
+
+    /// Demo handler: GET /whoami (message source as seen by the runtime, plus
+    /// the client socket address when the HTTP server reports one)
+    #[http(method = "GET", path = "/whoami")]
+    fn whoami(&mut self) -> ApiResponse {
+        self.count_call();
+        let source = current_source().unwrap_or_else(|| "unknown".to_string());
+        let client = current_http_request()
+            .and_then(|request| request.source_socket_addr().ok())
+            .map(|addr| addr.to_string());
+        kiprintln!("GET /whoami: {} (client {:?})", source, client);
+        let data = match client {
+            Some(client) => format!("{} (client {})", source, client),
+            None => source,
+        };
+        ApiResponse::new(&data)
+    }
+
+    /// Demo handler: POST /delay-random (sleeps a pseudo-random time in
+    /// `[min_ms, max_ms]`, capped at `MAX_DELAY_MS`, then reports it; a
+    /// `min_ms` above the cap is rejected)
+    #[http(method = "POST", path = "/delay-random")]
+    async fn delay_random(&mut self, req: RangeRequest) -> Result<ApiResponse, String> {
+        self.count_call();
+        if req.min_ms > req.max_ms {
+            return Err(format!(
+                "min_ms {} exceeds max_ms {}",
+                req.min_ms, req.max_ms
+            ));
+        }
+        if req.min_ms > MAX_DELAY_MS {
+            return Err(format!(
+                "min_ms {} exceeds the {}ms delay cap",
+                req.min_ms, MAX_DELAY_MS
+            ));
+        }
+        // No rng dependency: the sub-second clock is jittery enough for this
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.subsec_nanos() as u64)
+            .unwrap_or_default();
+        let span = req.max_ms - req.min_ms;
+        let delay_ms = (req.min_ms + seed % span.saturating_add(1)).min(MAX_DELAY_MS);
+        kiprintln!("POST /delay-random: sleeping {}ms", delay_ms);
+        sleep(delay_ms)
+            .await
+            .map_err(|e| format!("Sleep failed: {:?}", e))?;
+        Ok(ApiResponse::new(&format!("Delayed {}ms", delay_ms)))
+    }
+
+    /// Demo handler: POST /flaky (fails the first two calls of every three,
+    /// succeeds on the third)
+    #[http(method = "POST", path = "/flaky")]
+    fn flaky(&mut self) -> Result<ApiResponse, String> {
+        self.count_call();
+        self.flaky_counter = self.flaky_counter.wrapping_add(1);
+        kiprintln!("POST /flaky: call {}", self.flaky_counter);
+        if self.flaky_counter % 3 != 0 {
+            return Err("transient failure".to_string());
+        }
+        Ok(ApiResponse::new(&format!(
+            "succeeded on call {}",
+            self.flaky_counter
+        )))
+    }
+
+    /// Demo handler: POST /bulk-echo (total byte length of the strings; nothing is stored)
+    #[http(method = "POST", path = "/bulk-echo")]
+    async fn bulk_echo(&mut self, items: Vec<String>) -> Result<usize, String> {
+        self.count_call();
+        let total: usize = items.iter().map(|item| item.len()).sum();
+        kiprintln!("POST /bulk-echo: {} items, {} bytes", items.len(), total);
+        Ok(total)
+    }
+
+    /// Demo handler: POST /status (2xx codes return Ok, everything else Err)
+    #[http(method = "POST", path = "/status")]
+    async fn force_status(&mut self, req: StatusRequest) -> Result<ApiResponse, String> {
+        self.count_call();
+        kiprintln!("POST /status: {}", req.code);
+        match req.code {
+            200..=299 => Ok(ApiResponse::new(&format!("status {}", req.code))),
+            code => Err(format!("forced error {}", code)),
+        }
+    }
+
+    /// Demo handler: POST /echo-raw (returns the raw body as UTF-8, or hex if not UTF-8)
+    #[http(method = "POST", path = "/echo-raw")]
+    fn echo_raw(&mut self) -> ApiResponse {
+        self.count_call();
+        let bytes = get_blob().map(|blob| blob.bytes).unwrap_or_default();
+        kiprintln!("POST /echo-raw: {} bytes", bytes.len());
+        let data = match String::from_utf8(bytes) {
+            Ok(text) => text,
+            Err(e) => e
+                .into_bytes()
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect::<Vec<_>>()
+                .join(" "),
+        };
+        ApiResponse::new(&data)
+    }
+
+    /// Demo handler: POST /compute (squares the optional numeric id)
+    #[http(method = "POST", path = "/compute")]
+    async fn compute(&mut self, req: ApiRequest) -> Result<ApiResponse, String> {
+        self.count_call();
+        kiprintln!("POST /compute: {:?}", req);
+        let n = req.id.ok_or_else(|| "id is required".to_string())?;
+        let squared = n as u64 * n as u64;
+        Ok(ApiResponse::new(&format!("{} squared is {}", n, squared)))
+    }
+
     // -------------------------------------------------------------------------
 // DYNAMIC ROUTING HANDLERS (for testing fallback behavior)
 // -------------------------------------------------------------------------
 
-/// Fallback handler for API GET requests - NO PATH, uses get_path() internally
+/// Fallback handler for API GET requests - NO PATH, uses get_path() internally.
+/// Only claims /api/, /admin/ and /test/ paths. Any other path still gets its
+/// response from here, but only an error, and is left out of the call metrics.
 #[http(method = "GET")]
-fn handle_api_get_fallback(&mut self) -> ApiResponse {
+fn handle_api_get_fallback(&mut self) -> Result<ApiResponse, String> {
     let path = get_path().unwrap_or_default();
     
     // Only handle paths we want to handle
-    if path.starts_with("/api/") {
+    let response = if path.starts_with("/api/") {
         kiprintln!("GET fallback for API: {}", path);
         ApiResponse::new(&format!("API GET fallback for {}", path))
     } else if path.starts_with("/admin/") {
@@ -377,16 +2983,20 @@ fn handle_api_get_fallback(&mut self) -> ApiResponse {
         kiprintln!("GET fallback for test: {}", path);
         ApiResponse::new(&format!("Test GET fallback for {}", path))
     } else {
-        // This will let other handlers (like catch-all) handle it
-        // But since this is a GET handler without path, it might interfere with UI
-        // So we should be more specific about what we handle
-        ApiResponse::new(&format!("Unexpected GET fallback for {}", path))
-    }
+        kiprintln!("GET fallback declined for {}", path);
+        return Err(format!("No GET handler for {}", path));
+    };
+    self.count_call();
+    self.record_method();
+
+    Ok(response.with_query().with_headers())
 }
 
 /// Fallback handler for POST requests - NO PATH, uses get_path() internally
 #[http(method = "POST")]
 async fn handle_post_fallback(&mut self, req: ApiRequest) -> Result<ApiResponse, String> {
+    self.count_call();
+    self.record_method();
     let path = get_path().unwrap_or_default();
     kiprintln!("POST fallback for: {} with data: {:?}", path, req);
 
@@ -394,17 +3004,23 @@ async fn handle_post_fallback(&mut self, req: ApiRequest) -> Result<ApiResponse,
         p if p.starts_with("/api/") => Ok(ApiResponse::new(&format!(
             "API POST to {} with: {}",
             p, req.message
-        ))),
+        ))
+        .with_request_id()
+        .with_headers()),
         _ => Ok(ApiResponse::new(&format!(
             "General POST to {} with: {}",
             path, req.message
-        ))),
+        ))
+        .with_request_id()
+        .with_headers()),
     }
 }
 
 /// Catch-all for non-GET methods - NO PATH, uses get_path() internally
 #[http(method = "PUT")]
 fn handle_put_fallback(&mut self) -> ApiResponse {
+    self.count_call();
+    self.record_method();
     let path = get_path().unwrap_or_default();
     let method = get_http_method().unwrap_or_default();
     kiprintln!("{} {} catch-all", method, path);
@@ -413,6 +3029,8 @@ fn handle_put_fallback(&mut self) -> ApiResponse {
 
 #[http(method = "DELETE")]
 fn handle_delete_fallback(&mut self) -> ApiResponse {
+    self.count_call();
+    self.record_method();
     let path = get_path().unwrap_or_default();
     let method = get_http_method().unwrap_or_default();
     kiprintln!("{} {} catch-all", method, path);
@@ -421,11 +3039,47 @@ fn handle_delete_fallback(&mut self) -> ApiResponse {
 
 #[http(method = "PATCH")]
 fn handle_patch_fallback(&mut self) -> ApiResponse {
+    self.count_call();
+    self.record_method();
     let path = get_path().unwrap_or_default();
     let method = get_http_method().unwrap_or_default();
     kiprintln!("{} {} catch-all", method, path);
     ApiResponse::new(&format!("Catch-all: {} {}", method, path))
 }
+
+/// Catch-all for HEAD probes - logs the request and returns an empty body
+#[http(method = "HEAD")]
+fn handle_head_fallback(&mut self) {
+    self.count_call();
+    self.record_method();
+    let path = get_path().unwrap_or_default();
+    let method = get_http_method().unwrap_or_default();
+    kiprintln!("{} {} catch-all", method, path);
+}
+
+/// Advertise the methods handled for the current path (CORS preflight / discovery)
+#[http(method = "OPTIONS")]
+fn handle_options_fallback(&mut self) -> ApiResponse {
+    self.count_call();
+    self.record_method();
+    let path = get_path().unwrap_or_default();
+    let methods = match path.as_str() {
+        "/users" => "GET, POST",
+        "/posts" | "/health" | "/ping" | "/version" | "/whoami" | "/time" => "GET",
+        "/api/data" | "/echo-delay" | "/status" | "/echo-raw" | "/compute" | "/slow-steps"
+        | "/delay-random" | "/flaky" | "/bulk-echo" => "POST",
+        _ => "GET, POST, PUT, DELETE, PATCH, HEAD",
+    };
+    kiprintln!("OPTIONS {}: {}", path, methods);
+    ApiResponse::new(methods)
+}
+
+/// Fallback-handler hit counts per HTTP method
+#[http]
+fn get_metrics(&self) -> HashMap<String, u64> {
+    self.count_call();
+    self.method_counts.clone()
+}
  
 
 }
@@ -83,7 +83,8 @@ function App() {
     ws.onopen = (event) => {
       console.log("WebSocket connection opened:", event);
       setWsConnected(true);
-      // Fetch initial tasks when WebSocket connects
+      // Opt in to task broadcasts, then fetch initial tasks
+      ws.send(JSON.stringify({ action: "subscribe" }));
       ws.send(JSON.stringify({ action: "get_tasks" }));
     };
 
@@ -94,7 +95,8 @@ function App() {
         console.log("Parsed WebSocket message:", data);
         
         // Handle different message types
-        if (data.type === "tasks_overview" || data.type === "task_added" || data.type === "task_toggled") {
+        if (data.type === "tasks_overview" || data.type === "task_added" || data.type === "tasks_added" || data.type === "task_toggled" ||
+            data.type === "task_edited" || data.type === "task_deleted") {
           if (data.tasks) {
             console.log("Updating tasks from WebSocket:", data.tasks);
             setTasks(data.tasks);
@@ -1,8 +1,18 @@
+// Task priority level, mirrors the Rust Priority enum
+export type Priority = "Low" | "Medium" | "High";
+
 // Define the structure for a single todo item
 export interface TodoItem {
   id: string;
   text: string;
   completed: boolean;
+  priority: Priority;
+  due_at: number | null; // Unix milliseconds
+  tags: string[];
+  parent_id: string | null;
+  version: number; // Bumped on every mutation
+  created_at_ms: number; // Unix milliseconds
+  updated_at_ms: number; // Unix milliseconds of the last change
 }
 
 // Define the type for the state managed by the Zustand store
@@ -14,7 +24,7 @@ export interface TodoState {
 
 // Request body for the add_task endpoint
 export interface AddTaskRequest {
-    AddTask: string; // Key matches the Rust function name, value is the task text
+    AddTask: [string, Priority | null, string | null]; // Key matches the Rust function name, value is [text, priority, parent_id]
 }
 
 // Request body for the get_tasks endpoint
@@ -25,15 +35,22 @@ export interface GetTasksRequest {
 
 // Request body for the toggle_task endpoint
 export interface ToggleTaskRequest {
-    ToggleTask: string; // Key matches the Rust function name, value is the task ID
+    ToggleTask: [string, number]; // Key matches the Rust function name, value is [task ID, expected version]
 }
 
 
 // --- Response Types ---
-// Generic response wrapper for Rust Result<T, E> where E is String
+// Mirrors the Rust TodoError enum; the key tells the error kind
+export type TodoError =
+  | { NotFound: string }
+  | { Validation: string }
+  | { Conflict: string }
+  | { Internal: string };
+
+// Generic response wrapper for Rust Result<T, E> where E is TodoError
 interface RustResponse<T> {
   Ok?: T;
-  Err?: string;
+  Err?: TodoError;
 }
 
 // Response type for the add_task endpoint